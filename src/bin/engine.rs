@@ -0,0 +1,121 @@
+// Local game engine.
+//
+// Plays full games on the simulator without the official engine or a network connection. Each
+// snake is either an in-process strategy or a snake server reached over HTTP, so a running
+// copy of this server can be tested end to end:
+//
+//   engine --snake http://127.0.0.1:8080 --snake search:2
+//
+// Snakes: logic (the server's move logic, in-process), search[:depth[:weight file]], random, or
+// an http://host:port URL. With --snakes N the given snakes are repeated to fill N seats.
+//
+// Usage: engine --snake SPEC [--snake SPEC ...] [--snakes N] [--games 1] [--seed 1]
+//               [--width 11] [--height 11] [--ruleset standard] [--map standard]
+//               [--max-turns 1000] [--timeout 500] [--minimum-food 1] [--food-spawn-chance 15]
+//               [--hazard-damage 14] [--shrink-every 25]
+
+use battlesnake_rst::sim::engine::{self, snake_id, spawn_points, EngineConfig, GameOutcome, Player};
+
+struct Options {
+    specs: Vec<String>,
+    snakes: Option<usize>,
+    games: u32,
+    game: EngineConfig,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options { specs: Vec::new(), snakes: None, games: 1, game: EngineConfig { seed: 1, ..EngineConfig::default() } };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+        let number = |v: String| v.parse::<u64>().map_err(|e| format!("{}: {}", flag, e));
+        let settings = &mut options.game.settings;
+        match flag.as_str() {
+            "--snake" => options.specs.push(value()?),
+            "--snakes" => options.snakes = Some(number(value()?)? as usize),
+            "--games" => options.games = number(value()?)?.max(1) as u32,
+            "--seed" => options.game.seed = number(value()?)?,
+            "--width" => options.game.width = number(value()?)? as i32,
+            "--height" => options.game.height = number(value()?)? as i32,
+            "--ruleset" => options.game.ruleset = value()?,
+            "--map" => options.game.map = value()?,
+            "--max-turns" => options.game.max_turns = number(value()?)? as u32,
+            "--timeout" => options.game.timeout_ms = number(value()?)? as u32,
+            "--minimum-food" => settings.minimum_food = number(value()?)? as u32,
+            "--food-spawn-chance" => settings.food_spawn_chance = number(value()?)? as u32,
+            "--hazard-damage" => settings.hazard_damage_per_turn = number(value()?)? as u32,
+            "--shrink-every" => settings.royale.shrink_every_n_turns = number(value()?)? as u32,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    if options.specs.is_empty() {
+        return Err("at least one --snake is needed".to_string());
+    }
+    let seats = options.snakes.unwrap_or(options.specs.len());
+    let spawns = spawn_points(options.game.width, options.game.height).len();
+    if seats == 0 || seats > spawns {
+        return Err(format!("between 1 and {} snakes can play", spawns));
+    }
+    if options.game.width < 3 || options.game.height < 3 {
+        return Err("the board must be at least 3x3".to_string());
+    }
+    options.specs = options.specs.iter().cycle().take(seats).cloned().collect();
+    Ok(options)
+}
+
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
+
+    let options = match parse_options() {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("engine: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let players = match options.specs.iter().map(|s| Player::parse(s, &options.game.ruleset)).collect::<Result<Vec<_>, _>>() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("engine: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    println!(
+        "{} game(s) of {} on {} {}x{}: {}",
+        options.games, options.game.ruleset, options.game.map, options.game.width, options.game.height,
+        options.specs.iter().enumerate().map(|(i, s)| format!("{} = {}", snake_id(i), s)).collect::<Vec<_>>().join(", ")
+    );
+
+    let mut wins = vec![0u32; players.len()];
+    let mut draws = 0;
+    for game in 0..options.games {
+        let config = EngineConfig { seed: options.game.seed.wrapping_add(game as u64), ..options.game.clone() };
+        let result = match engine::run_game(&config, &players) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("engine: {}", e);
+                std::process::exit(2);
+            }
+        };
+        match result.outcome {
+            GameOutcome::Winner(i) => {
+                wins[i] += 1;
+                println!("game {} (seed {}): {} ({}) wins after {} turns", game + 1, config.seed, snake_id(i), options.specs[i], result.turns);
+            }
+            GameOutcome::Draw => {
+                draws += 1;
+                println!("game {} (seed {}): draw after {} turns", game + 1, config.seed, result.turns);
+            }
+        }
+    }
+
+    if options.games > 1 {
+        for (i, w) in wins.iter().enumerate() {
+            println!("{} ({}): {} wins", snake_id(i), options.specs[i], w);
+        }
+        println!("draws: {}", draws);
+    }
+}
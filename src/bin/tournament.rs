@@ -0,0 +1,198 @@
+// Self-play tournament runner.
+//
+// Plays duels between named strategy configurations on the local engine, alternating seats and
+// cycling through seeds and board sizes, then prints Elo ratings and win/draw/loss tables. With
+// --sprt exactly two players take part, the first being the patch and the second the baseline,
+// and play stops as soon as a sequential probability ratio test accepts or rejects the patch
+// (or after --games games):
+//
+//   tournament --player patch=search:2:tuned.json --player base=search:2 --sprt 0,10
+//
+// Players are NAME=SPEC, with SPEC as for the engine binary: logic, search[:depth[:weight file]],
+// random or an http://host:port URL.
+//
+// Usage: tournament --player NAME=SPEC --player NAME=SPEC [...] [--games 100] [--seed 1]
+//                   [--sizes 11x11,7x7] [--ruleset standard] [--map standard] [--max-turns 500]
+//                   [--sprt ELO0,ELO1] [--alpha 0.05] [--beta 0.05]
+
+use battlesnake_rst::sim::engine::{self, EngineConfig, Player};
+use battlesnake_rst::sim::stats::{self, MatchScore, Sprt, SprtDecision};
+
+struct Options {
+    names: Vec<String>,
+    specs: Vec<String>,
+    games: u32, // Per pairing; the most an SPRT may take
+    sizes: Vec<(i32, i32)>,
+    sprt: Option<(f64, f64)>,
+    alpha: f64,
+    beta: f64,
+    game: EngineConfig,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        names: Vec::new(),
+        specs: Vec::new(),
+        games: 100,
+        sizes: vec![(11, 11)],
+        sprt: None,
+        alpha: 0.05,
+        beta: 0.05,
+        game: EngineConfig { seed: 1, max_turns: 500, ..EngineConfig::default() },
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+        let number = |v: String| v.parse::<u64>().map_err(|e| format!("{}: {}", flag, e));
+        let real = |v: &str| v.trim().parse::<f64>().map_err(|e| format!("{}: {}", flag, e));
+        match flag.as_str() {
+            "--player" => {
+                let player = value()?;
+                let (name, spec) = player.split_once('=').ok_or_else(|| format!("--player {}: expected NAME=SPEC", player))?;
+                options.names.push(name.to_string());
+                options.specs.push(spec.to_string());
+            }
+            "--games" => options.games = number(value()?)?.max(2) as u32,
+            "--seed" => options.game.seed = number(value()?)?,
+            "--sizes" => {
+                options.sizes = value()?.split(',').map(|size| {
+                    let (w, h) = size.split_once('x').ok_or_else(|| format!("--sizes: bad size '{}'", size))?;
+                    let (w, h) = (number(w.to_string())? as i32, number(h.to_string())? as i32);
+                    if w < 3 || h < 3 {
+                        return Err(format!("--sizes: {} is smaller than 3x3", size));
+                    }
+                    Ok((w, h))
+                }).collect::<Result<_, String>>()?;
+            }
+            "--ruleset" => options.game.ruleset = value()?,
+            "--map" => options.game.map = value()?,
+            "--max-turns" => options.game.max_turns = number(value()?)? as u32,
+            "--sprt" => {
+                let bounds = value()?;
+                let (elo0, elo1) = bounds.split_once(',').ok_or("--sprt: expected ELO0,ELO1")?;
+                options.sprt = Some((real(elo0)?, real(elo1)?));
+            }
+            "--alpha" => options.alpha = real(&value()?)?,
+            "--beta" => options.beta = real(&value()?)?,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+
+    if options.names.len() < 2 {
+        return Err("at least two --player entries are needed".to_string());
+    }
+    if options.sprt.is_some() && options.names.len() != 2 {
+        return Err("--sprt compares exactly two players".to_string());
+    }
+    if let Some((elo0, elo1)) = options.sprt {
+        if elo1 <= elo0 {
+            return Err("--sprt: ELO1 must be above ELO0".to_string());
+        }
+    }
+    Ok(options)
+}
+
+// Plays a pair of games between players `a` and `b` on the same seed, once from each seat, and
+// adds them to `a`'s score against `b`.
+fn play_pair(players: &[Player], a: usize, b: usize, config: &EngineConfig, score: &mut MatchScore) -> Result<(), String> {
+    for a_seat in 0..2 {
+        let seats = if a_seat == 0 { [a, b] } else { [b, a] };
+        let duel = [players[seats[0]].clone(), players[seats[1]].clone()];
+        score.record(engine::run_game(config, &duel)?.outcome, a_seat);
+    }
+    Ok(())
+}
+
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
+
+    let options = match parse_options() {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("tournament: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let players = match options.specs.iter().map(|s| Player::parse(s, &options.game.ruleset)).collect::<Result<Vec<_>, _>>() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("tournament: {}", e);
+            std::process::exit(2);
+        }
+    };
+    let sprt = options.sprt.map(|(elo0, elo1)| Sprt::new(elo0, elo1, options.alpha, options.beta));
+
+    let n = players.len();
+    let pairings: Vec<(usize, usize)> = (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b))).collect();
+    println!(
+        "{} games per pairing of {} on {} over {} board size(s): {}",
+        options.games, options.game.ruleset, options.game.map, options.sizes.len(),
+        options.names.iter().zip(&options.specs).map(|(n, s)| format!("{} = {}", n, s)).collect::<Vec<_>>().join(", ")
+    );
+    if let Some(sprt) = &sprt {
+        println!("SPRT elo0 {} elo1 {}, LLR bounds [{:.2}, {:.2}]", sprt.elo0, sprt.elo1, sprt.lower, sprt.upper);
+    }
+
+    // scores[a][b]: a's results against b. Only a < b is played into; the rest is mirrored.
+    let mut scores = vec![vec![MatchScore::default(); n]; n];
+    let mut decision = SprtDecision::Continue;
+    for round in 0..options.games.div_ceil(2) {
+        let (width, height) = options.sizes[round as usize % options.sizes.len()];
+        let config = EngineConfig { width, height, seed: options.game.seed.wrapping_add(round as u64), ..options.game.clone() };
+        for &(a, b) in &pairings {
+            if let Err(e) = play_pair(&players, a, b, &config, &mut scores[a][b]) {
+                eprintln!("tournament: {}", e);
+                std::process::exit(2);
+            }
+            scores[b][a] = scores[a][b].reversed();
+        }
+
+        if let Some(sprt) = &sprt {
+            let score = &scores[0][1];
+            decision = sprt.decide(score);
+            println!(
+                "{} games: {}W/{}D/{}L, LLR {:.2}",
+                score.games(), score.wins, score.draws, score.losses, sprt.llr(score)
+            );
+            if decision != SprtDecision::Continue {
+                break;
+            }
+        }
+    }
+
+    println!();
+    println!("{:<16} {:>7} {:>6} {:>5} {:>5} {:>5} {:>7}", "player", "elo", "games", "W", "D", "L", "score");
+    let elo = stats::ratings(&scores);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| elo[b].total_cmp(&elo[a]));
+    for &i in &order {
+        let total = scores[i].iter().fold(MatchScore::default(), |t, s| MatchScore {
+            wins: t.wins + s.wins,
+            draws: t.draws + s.draws,
+            losses: t.losses + s.losses,
+        });
+        println!(
+            "{:<16} {:>+7.1} {:>6} {:>5} {:>5} {:>5} {:>6.1}%",
+            options.names[i], elo[i], total.games(), total.wins, total.draws, total.losses, total.score() * 100.0
+        );
+    }
+
+    println!();
+    for &(a, b) in &pairings {
+        let score = &scores[a][b];
+        let (diff, low, high) = score.elo();
+        println!(
+            "{} vs {}: {}W/{}D/{}L, score {:.1}%, elo {:+.1} (95% CI {:+.1}..{:+.1})",
+            options.names[a], options.names[b], score.wins, score.draws, score.losses,
+            score.score() * 100.0, diff, low, high
+        );
+    }
+
+    match (sprt, decision) {
+        (Some(_), SprtDecision::Accept) => println!("SPRT: H1 accepted, {} is an improvement", options.names[0]),
+        (Some(_), SprtDecision::Reject) => println!("SPRT: H0 accepted, {} is not an improvement", options.names[0]),
+        (Some(_), SprtDecision::Continue) => println!("SPRT: inconclusive after {} games", scores[0][1].games()),
+        (None, _) => {}
+    }
+}
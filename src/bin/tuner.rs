@@ -0,0 +1,196 @@
+// Evaluation weight tuner.
+//
+// Tunes the early/mid/late weight sets of a ruleset's weight profile with SPSA (simultaneous
+// perturbation stochastic approximation): every iteration plays a short match between two
+// randomly perturbed copies of the current weights and steps towards the winner. The tuned
+// profile is then played against the baseline and written out as a weight file that the
+// server loads through EVAL_WEIGHTS_FILE.
+//
+// Games are played on the local engine under the ruleset being tuned, with its map and settings.
+//
+// Usage: tuner [--ruleset standard] [--baseline FILE] [--out tuned_weights.json]
+//              [--iterations 50] [--games-per-iteration 4] [--eval-games 100]
+//              [--depth 2] [--width 11] [--height 11] [--max-turns 300] [--seed 1]
+//              [--map standard] [--minimum-food 1] [--food-spawn-chance 15]
+//              [--hazard-damage 14] [--shrink-every 25]
+
+use battlesnake_rst::logic::evaluation::Feature;
+use battlesnake_rst::logic::weights::{self, EvalWeights, WeightProfile};
+use battlesnake_rst::sim::engine::{self, EngineConfig, Player};
+use battlesnake_rst::sim::stats::MatchScore;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// SPSA gain schedule (Spall's recommended exponents).
+const SPSA_A: f32 = 0.2; // Step size, relative to each weight's magnitude
+const SPSA_C: f32 = 0.1; // Perturbation size, relative to each weight's magnitude
+const SPSA_STABILITY: f32 = 10.0;
+const SPSA_ALPHA: f32 = 0.602;
+const SPSA_GAMMA: f32 = 0.101;
+
+struct Options {
+    baseline: Option<String>,
+    out: String,
+    iterations: u32,
+    games_per_iteration: u32,
+    eval_games: u32,
+    seed: u64,
+    depth: u8,
+    game: EngineConfig,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        baseline: None,
+        out: "tuned_weights.json".to_string(),
+        iterations: 50,
+        games_per_iteration: 4,
+        eval_games: 100,
+        seed: 1,
+        depth: 2,
+        game: EngineConfig { max_turns: 300, ..EngineConfig::default() },
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", flag));
+        let number = |v: String| v.parse::<u64>().map_err(|e| format!("{}: {}", flag, e));
+        let settings = &mut options.game.settings;
+        match flag.as_str() {
+            "--ruleset" => options.game.ruleset = value()?,
+            "--map" => options.game.map = value()?,
+            "--baseline" => options.baseline = Some(value()?),
+            "--out" => options.out = value()?,
+            "--iterations" => options.iterations = number(value()?)? as u32,
+            "--games-per-iteration" => options.games_per_iteration = number(value()?)?.max(1) as u32,
+            "--eval-games" => options.eval_games = number(value()?)? as u32,
+            "--depth" => options.depth = number(value()?)?.max(1) as u8,
+            "--width" => options.game.width = number(value()?)? as i32,
+            "--height" => options.game.height = number(value()?)? as i32,
+            "--max-turns" => options.game.max_turns = number(value()?)? as u32,
+            "--seed" => options.seed = number(value()?)?,
+            "--minimum-food" => settings.minimum_food = number(value()?)? as u32,
+            "--food-spawn-chance" => settings.food_spawn_chance = number(value()?)? as u32,
+            "--hazard-damage" => settings.hazard_damage_per_turn = number(value()?)? as u32,
+            "--shrink-every" => settings.royale.shrink_every_n_turns = number(value()?)? as u32,
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    Ok(options)
+}
+
+// The tuned parameters: every weight of the early, mid and late sets, in that order.
+fn phase_sets(profile: &WeightProfile) -> [&EvalWeights; 3] {
+    [&profile.early, &profile.mid, &profile.late]
+}
+
+fn to_params(profile: &WeightProfile) -> Vec<f32> {
+    phase_sets(profile).iter()
+        .flat_map(|w| Feature::ALL.iter().map(move |&f| w.weight(f)))
+        .collect()
+}
+
+fn from_params(base: &WeightProfile, params: &[f32]) -> WeightProfile {
+    let mut profile = base.clone();
+    let sets = [&mut profile.early, &mut profile.mid, &mut profile.late];
+    for (set, chunk) in sets.into_iter().zip(params.chunks(Feature::ALL.len())) {
+        for (&feature, &value) in Feature::ALL.iter().zip(chunk) {
+            set.set_weight(feature, value);
+        }
+    }
+    profile
+}
+
+// Plays `games` duels between `a` and `b`, searching `depth` plies and swapping seats every game.
+fn play_match(a: &WeightProfile, b: &WeightProfile, games: u32, depth: u8, config: &EngineConfig, seed: u64) -> Result<MatchScore, String> {
+    let player = |profile: &WeightProfile| Player::Search { depth, profile: Some(Box::new(profile.clone())) };
+    let mut score = MatchScore::default();
+    for game in 0..games {
+        let a_seat = (game % 2) as usize;
+        let players = if a_seat == 0 { [player(a), player(b)] } else { [player(b), player(a)] };
+        let game_config = EngineConfig { seed: seed.wrapping_add(game as u64 / 2), ..config.clone() };
+        score.record(engine::run_game(&game_config, &players)?.outcome, a_seat);
+    }
+    Ok(score)
+}
+
+fn fail(error: &str) -> ! {
+    eprintln!("tuner: {}", error);
+    std::process::exit(2);
+}
+
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
+
+    let options = match parse_options() {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("tuner: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let baseline = match &options.baseline {
+        Some(path) => match weights::load_weight_file(path) {
+            Ok(profiles) => profiles.get(&options.game.ruleset).or_else(|| profiles.get("default")).cloned()
+                .unwrap_or_else(|| weights::profile_for_ruleset(&options.game.ruleset)),
+            Err(e) => {
+                eprintln!("tuner: could not read baseline {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => weights::profile_for_ruleset(&options.game.ruleset),
+    };
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut params = to_params(&baseline);
+    // Perturbations scale with each weight so small and large weights move proportionally.
+    let scale: Vec<f32> = params.iter().map(|p| p.abs().max(1.0)).collect();
+
+    println!(
+        "Tuning {} weights for '{}' over {} iterations ({} games each, depth {})",
+        params.len(), options.game.ruleset, options.iterations, options.games_per_iteration, options.depth
+    );
+
+    for k in 0..options.iterations {
+        let a_k = SPSA_A / (k as f32 + 1.0 + SPSA_STABILITY).powf(SPSA_ALPHA);
+        let c_k = SPSA_C / (k as f32 + 1.0).powf(SPSA_GAMMA);
+        let delta: Vec<f32> = params.iter().map(|_| if rng.gen_bool(0.5) { 1.0 } else { -1.0 }).collect();
+
+        let perturbed = |sign: f32| -> Vec<f32> {
+            params.iter().zip(&delta).zip(&scale).map(|((p, d), s)| p + sign * c_k * d * s).collect()
+        };
+        let plus = from_params(&baseline, &perturbed(1.0));
+        let minus = from_params(&baseline, &perturbed(-1.0));
+
+        let result = play_match(&plus, &minus, options.games_per_iteration, options.depth, &options.game, rng.gen())
+            .unwrap_or_else(|e| fail(&e));
+        // +1 when plus won everything, -1 when minus did.
+        let signal = (result.score() * 2.0 - 1.0) as f32;
+        for ((p, d), s) in params.iter_mut().zip(&delta).zip(&scale) {
+            *p += a_k * signal * d * s;
+        }
+        println!("iteration {:>4}: plus {}W/{}D/{}L", k + 1, result.wins, result.draws, result.losses);
+    }
+
+    let tuned = from_params(&baseline, &params);
+    let result = play_match(&tuned, &baseline, options.eval_games, options.depth, &options.game, rng.gen())
+        .unwrap_or_else(|e| fail(&e));
+    let (low, high) = result.confidence_interval();
+    println!(
+        "Tuned vs baseline over {} games: {}W/{}D/{}L, score {:.1}% (95% CI {:.1}%..{:.1}%)",
+        result.games(), result.wins, result.draws, result.losses,
+        result.score() * 100.0, low * 100.0, high * 100.0
+    );
+
+    let file: HashMap<&str, &WeightProfile> = HashMap::from([(options.game.ruleset.as_str(), &tuned)]);
+    let json = serde_json::to_string_pretty(&file).expect("weight profiles serialize");
+    match std::fs::write(&options.out, json) {
+        Ok(()) => println!("Wrote tuned weights to {}", options.out),
+        Err(e) => {
+            eprintln!("tuner: could not write {}: {}", options.out, e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -48,22 +48,87 @@ impl Coord {
             Move::Right => Coord { x: self.x + 1, y: self.y },
         }
     }
+
+    // The move that takes us from this coordinate to an adjacent one, if they are adjacent
+    pub fn direction_to(&self, other: &Coord) -> Option<Move> {
+        [Move::Up, Move::Down, Move::Left, Move::Right]
+            .into_iter()
+            .find(|&m| self.apply_move(m) == *other)
+    }
+
+    // Manhattan distance to another coordinate
+    pub fn manhattan_distance(&self, other: &Coord) -> u32 {
+        ((self.x - other.x).abs() + (self.y - other.y).abs()) as u32
+    }
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Game {
     pub id: String,
     pub ruleset: Ruleset,
     pub timeout: u32,
+    #[serde(default)]
+    pub map: String, // Engine map the game is played on, e.g. "standard" or "hz_spiral"
     // source: Option<String>, // Consider adding if needed based on API version
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Ruleset {
     pub name: String,
     pub version: String,
-    // settings: Option<serde_json::Value>, // Use generic Value for flexibility
+    #[serde(default)]
+    pub settings: RulesetSettings,
+}
+
+// Ruleset settings sent by the engine. Missing fields fall back to the engine's defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RulesetSettings {
+    pub food_spawn_chance: u32,      // Percent chance of spawning food each turn
+    pub minimum_food: u32,           // Food is topped up to this many items
+    pub hazard_damage_per_turn: u32, // Extra health lost for every turn a head ends in a hazard
+    pub royale: RoyaleSettings,
+    pub squad: SquadSettings,
+}
+
+impl Default for RulesetSettings {
+    fn default() -> Self {
+        RulesetSettings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            royale: RoyaleSettings::default(),
+            squad: SquadSettings::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RoyaleSettings {
+    pub shrink_every_n_turns: u32, // The hazard grows in from one edge this often
+}
+
+impl Default for RoyaleSettings {
+    fn default() -> Self {
+        RoyaleSettings { shrink_every_n_turns: 25 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SquadSettings {
+    pub allow_body_collisions: bool, // Squadmates can move through each other's bodies
+    pub shared_elimination: bool,    // When one squadmate is eliminated, the whole squad is
+    pub shared_health: bool,         // Squadmates all have the health of the healthiest
+    pub shared_length: bool,         // Squadmates all have the length of the longest
+}
+
+impl Default for SquadSettings {
+    fn default() -> Self {
+        SquadSettings { allow_body_collisions: true, shared_elimination: true, shared_health: true, shared_length: true }
+    }
 }
 
 
@@ -75,9 +140,10 @@ pub struct Battlesnake {
     pub body: Vec<Coord>,
     pub head: Coord,
     pub length: u32,
+    #[serde(default)]
+    pub squad: String, // Empty outside squad games
     // latency: Option<String>,
     // shout: Option<String>,
-    // squad: Option<String>,
     // customizations: Option<serde_json::Value>,
 }
 
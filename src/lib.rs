@@ -0,0 +1,3 @@
+pub mod game_state;
+pub mod logic;
+pub mod sim;
@@ -0,0 +1,281 @@
+use crate::game_state::Coord;
+use crate::sim::state::SimState;
+use super::flood_fill;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Structure of the free space on the board: which free cells are chokepoints (articulation
+// points) and how the free cells split into biconnected components.
+#[derive(Debug, Clone, Default)]
+pub struct FreeSpaceAnalysis {
+    pub free: HashSet<Coord>,
+    // Cut cells mapped to the sizes of the regions that become disconnected when the cell is filled.
+    pub articulation_points: HashMap<Coord, Vec<usize>>,
+    // Cells of each biconnected component. Cut cells appear in every component they join.
+    pub biconnected_components: Vec<Vec<Coord>>,
+}
+
+impl FreeSpaceAnalysis {
+    pub fn is_cut_cell(&self, coord: &Coord) -> bool {
+        self.articulation_points.contains_key(coord)
+    }
+}
+
+// Builds the free-cell graph of `state` (in-bounds cells not blocked by bodies next turn) and runs
+// Tarjan's algorithm over it to find articulation points and biconnected components.
+pub fn analyze_free_space(state: &SimState) -> FreeSpaceAnalysis {
+    let blocked = flood_fill::static_obstacles(state);
+    analyze_cells(state, |c| !blocked.contains(c))
+}
+
+// Chokepoints of the map itself: the same analysis over the squares outside every hazard, with
+// snakes left out. On hazard maps these are the gaps and bridges that shut snakes out of safe
+// ground when held.
+pub fn analyze_map(state: &SimState) -> FreeSpaceAnalysis {
+    analyze_cells(state, |c| !state.hazards.contains(c))
+}
+
+// Tarjan's algorithm over the graph of the board's cells for which `open` holds.
+fn analyze_cells(state: &SimState, open: impl Fn(&Coord) -> bool) -> FreeSpaceAnalysis {
+    let cells: Vec<Coord> = (0..state.width)
+        .flat_map(|x| (0..state.height).map(move |y| Coord { x, y }))
+        .filter(|c| open(c))
+        .collect();
+    let index: HashMap<Coord, usize> = cells.iter().enumerate().map(|(i, c)| (*c, i)).collect();
+    let adjacency: Vec<Vec<usize>> = cells.iter()
+        .map(|c| state.neighbours(c).filter_map(|n| index.get(&n).copied()).collect())
+        .collect();
+
+    let n = cells.len();
+    let mut disc: Vec<Option<u32>> = vec![None; n];
+    let mut low = vec![0u32; n];
+    let mut subtree_size = vec![1usize; n];
+    // For each vertex, the subtree sizes of children that cannot reach above it.
+    let mut separated: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut timer = 0u32;
+
+    let mut articulation_points = HashMap::new();
+    let mut biconnected_components = Vec::new();
+    let mut edge_stack: Vec<(usize, usize)> = Vec::new();
+
+    for root in 0..n {
+        if disc[root].is_some() {
+            continue;
+        }
+        disc[root] = Some(timer);
+        low[root] = timer;
+        timer += 1;
+        if adjacency[root].is_empty() {
+            biconnected_components.push(vec![cells[root]]);
+            continue;
+        }
+
+        // Iterative DFS: (vertex, parent, next neighbour index to look at)
+        let mut stack: Vec<(usize, Option<usize>, usize)> = vec![(root, None, 0)];
+        while let Some(&mut (v, parent, ref mut next)) = stack.last_mut() {
+            if let Some(&w) = adjacency[v].get(*next) {
+                *next += 1;
+                match disc[w] {
+                    None => {
+                        disc[w] = Some(timer);
+                        low[w] = timer;
+                        timer += 1;
+                        edge_stack.push((v, w));
+                        stack.push((w, Some(v), 0));
+                    }
+                    Some(dw) if Some(w) != parent && dw < disc[v].unwrap_or(0) => {
+                        // Back edge to an ancestor
+                        low[v] = low[v].min(dw);
+                        edge_stack.push((v, w));
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // All neighbours of v done: fold it into its parent.
+            stack.pop();
+            let Some(u) = parent else { continue };
+            low[u] = low[u].min(low[v]);
+            subtree_size[u] += subtree_size[v];
+            if low[v] >= disc[u].unwrap_or(0) {
+                separated[u].push(subtree_size[v]);
+                let mut component: HashSet<Coord> = HashSet::new();
+                while let Some((a, b)) = edge_stack.pop() {
+                    component.insert(cells[a]);
+                    component.insert(cells[b]);
+                    if (a, b) == (u, v) {
+                        break;
+                    }
+                }
+                biconnected_components.push(component.into_iter().collect());
+            }
+        }
+
+        // A non-root vertex is a cut cell if any child subtree hangs off it alone;
+        // the root only if it has at least two such children.
+        let component_size = subtree_size[root];
+        for v in 0..n {
+            if separated[v].is_empty() || (v == root && separated[v].len() < 2) {
+                continue;
+            }
+            let mut regions = std::mem::take(&mut separated[v]);
+            if v != root {
+                let rest = component_size - 1 - regions.iter().sum::<usize>();
+                if rest > 0 {
+                    regions.push(rest);
+                }
+            }
+            articulation_points.insert(cells[v], regions);
+        }
+        separated.iter_mut().for_each(Vec::clear);
+    }
+
+    FreeSpaceAnalysis {
+        free: cells.into_iter().collect(),
+        articulation_points,
+        biconnected_components,
+    }
+}
+
+// BFS distances over free cells from every source, which need not be free themselves (e.g. heads).
+fn distances_from(state: &SimState, analysis: &FreeSpaceAnalysis, sources: &[Coord], avoid: Option<&Coord>) -> HashMap<Coord, u32> {
+    let mut dist: HashMap<Coord, u32> = sources.iter().map(|s| (*s, 0)).collect();
+    let mut queue: VecDeque<Coord> = sources.iter().cloned().collect();
+    while let Some(p) = queue.pop_front() {
+        let d = dist[&p];
+        for neighbor in state.neighbours(&p) {
+            if !analysis.free.contains(&neighbor) || Some(&neighbor) == avoid || dist.contains_key(&neighbor) {
+                continue;
+            }
+            dist.insert(neighbor, d + 1);
+            queue.push_back(neighbor);
+        }
+    }
+    dist
+}
+
+// Checks whether `target`, which snake `snake_id` occupies `turns_to_target` turns from now, sits in
+// a pocket behind a cut cell that an opponent can reach no later than we can get back to it.
+// Returns the size of the smallest such pocket that is too small to hold the snake, if any.
+pub fn sealable_pocket(
+    state: &SimState,
+    analysis: &FreeSpaceAnalysis,
+    snake_id: &str,
+    target: &Coord,
+    turns_to_target: u32,
+) -> Option<usize> {
+    let snake = state.snakes.iter().find(|s| s.id == snake_id)?;
+    let opponent_heads: Vec<Coord> = state.opponents(snake_id)
+        .filter_map(|s| s.head().copied())
+        .collect();
+    if opponent_heads.is_empty() {
+        return None;
+    }
+    let opponent_dist = distances_from(state, analysis, &opponent_heads, None);
+    let our_dist = distances_from(state, analysis, &[*target], None);
+
+    analysis.articulation_points.keys()
+        .filter(|cut| *cut != target)
+        .filter_map(|cut| {
+            let our_turns = our_dist.get(cut)? + turns_to_target;
+            let their_turns = *opponent_dist.get(cut)?;
+            if their_turns > our_turns {
+                return None; // We get back out first
+            }
+            // The side of the cut cell we would be on; the target counts as a square of it.
+            let pocket = distances_from(state, analysis, &[*target], Some(cut)).len();
+            (pocket < snake.length()).then_some(pocket)
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    fn coords(cs: &[(i32, i32)]) -> Vec<Coord> {
+        cs.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
+
+    // A 5x5 board split down the middle by two snakes with stacked tails, leaving a gap at (2, 2).
+    fn split_board() -> SimState {
+        SimState::empty(5, 5).with_snakes(vec![
+            SimSnake::new("top", &[(2, 4), (2, 3), (2, 3)]),
+            SimSnake::new("bottom", &[(2, 0), (2, 1), (2, 1)]),
+        ])
+    }
+
+    fn sorted(mut values: Vec<usize>) -> Vec<usize> {
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn cut_cells_split_the_board_into_regions() {
+        let analysis = analyze_free_space(&split_board());
+        assert_eq!(analysis.free.len(), 21);
+
+        let mut cut: Vec<Coord> = analysis.articulation_points.keys().copied().collect();
+        cut.sort_by_key(|c| (c.x, c.y));
+        assert_eq!(cut, coords(&[(1, 2), (2, 2), (3, 2)]));
+
+        // Filling the gap leaves the two halves; filling the square beside it cuts one half off
+        // from the gap and the other half.
+        assert_eq!(sorted(analysis.articulation_points[&Coord { x: 2, y: 2 }].clone()), vec![10, 10]);
+        assert_eq!(sorted(analysis.articulation_points[&Coord { x: 1, y: 2 }].clone()), vec![9, 11]);
+        assert!(!analysis.is_cut_cell(&Coord { x: 0, y: 2 }));
+    }
+
+    #[test]
+    fn biconnected_components_meet_at_cut_cells() {
+        let analysis = analyze_free_space(&split_board());
+        // Each half is a 2x5 ladder; the gap joins them through two single-edge components.
+        let sizes = sorted(analysis.biconnected_components.iter().map(Vec::len).collect());
+        assert_eq!(sizes, vec![2, 2, 10, 10]);
+        let gap = Coord { x: 2, y: 2 };
+        assert_eq!(analysis.biconnected_components.iter().filter(|c| c.contains(&gap)).count(), 2);
+    }
+
+    #[test]
+    fn pockets_an_opponent_can_seal_are_found() {
+        // A dead end at the left edge behind (1, 1). "them" has its head beside the door, and our
+        // snake of length 6 is four moves from (0, 1).
+        let me = SimSnake::new("me", &[(4, 1), (5, 1), (6, 1), (6, 0), (5, 0), (4, 0)]);
+        let them = SimSnake::new("them", &[(1, 2), (2, 2), (3, 2), (3, 2)]);
+        let wall = SimSnake::new("wall", &[(1, 0), (2, 0), (2, 0)]);
+        let state = SimState::empty(7, 3).with_snakes(vec![me.clone(), them.clone(), wall.clone()]);
+        let analysis = analyze_free_space(&state);
+        assert_eq!(sealable_pocket(&state, &analysis, "me", &Coord { x: 0, y: 1 }, 4), Some(3));
+
+        // Squadmates are not going to seal us in.
+        let squad = SimState::empty(7, 3).with_ruleset("squad").with_snakes(
+            [me.clone(), them, wall].into_iter().map(|s| s.with_squad("red")).collect(),
+        );
+        let analysis = analyze_free_space(&squad);
+        assert_eq!(sealable_pocket(&squad, &analysis, "me", &Coord { x: 0, y: 1 }, 4), None);
+
+        // With nobody else on the board there is nobody to seal it.
+        let alone = SimState::empty(7, 3).with_snakes(vec![me]);
+        let analysis = analyze_free_space(&alone);
+        assert_eq!(sealable_pocket(&alone, &analysis, "me", &Coord { x: 0, y: 1 }, 4), None);
+    }
+
+    #[test]
+    fn map_chokepoints_are_gaps_in_hazard_walls() {
+        // A hazard wall down the middle with one gap at (5, 5).
+        let wall: Vec<(i32, i32)> = (0..11).filter(|&y| y != 5).map(|y| (5, y)).collect();
+        let state = SimState::empty(11, 11)
+            .with_snakes(vec![SimSnake::new("me", &[(1, 1), (1, 0), (0, 0)])])
+            .with_hazards(&wall);
+        let map = analyze_map(&state);
+        // The gap and the squares either side of it are the only ways across.
+        let mut cut: Vec<Coord> = map.articulation_points.keys().copied().collect();
+        cut.sort_by_key(|c| (c.x, c.y));
+        assert_eq!(cut, coords(&[(4, 5), (5, 5), (6, 5)]));
+        assert_eq!(sorted(map.articulation_points[&Coord { x: 5, y: 5 }].clone()), vec![55, 55]);
+
+        // Snakes do not count: the map analysis ignores them.
+        assert!(map.free.contains(&Coord { x: 1, y: 1 }));
+    }
+}
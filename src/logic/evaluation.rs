@@ -1,62 +1,269 @@
 use crate::sim::state::{SimState, SimSnake}; // Use SimState
 use super::flood_fill; // Use flood_fill module
+use super::voronoi;
+use super::board_analysis;
+use super::food_race;
+use super::weights::EvalWeights;
 use log::debug;
 
 // Calculate controlled space for a specific snake in a SimState
 fn calculate_controlled_space(state: &SimState, snake: &SimSnake) -> usize {
     match snake.head() {
-        Some(head) => flood_fill::flood_fill_sim(state, head),
+        Some(head) => flood_fill::flood_fill_sim(state, &snake.id, head, 0),
         None => 0, // No space if snake has no head (is dead)
     }
 }
 
+// Terminal scores. Kept far outside the range of the heuristic score so a
+// decided game always outranks an undecided one; the ply adjustments below
+// stay well inside the gap between them.
+pub const WIN_SCORE: i32 = 1_000_000;
+pub const LOSS_SCORE: i32 = -1_000_000;
+// Everyone died on the same turn: better than dying alone, worse than surviving.
+pub const DRAW_SCORE: i32 = -500_000;
+
 // Evaluates a simulated game state from the perspective of the specified snake ID.
 // Higher scores are better.
-// TODO: Refine weights, add more factors (food proximity, opponent threats, center control, etc.)
-pub fn evaluate_sim_state(state: &SimState, our_id: &str) -> i32 {
+// `ply` is how far below the search root `state` sits: wins score higher the sooner
+// they happen and losses score higher the later they happen.
+// `root_opponents` is the number of opponents alive at the root, used to credit
+// opponents we outlived. Non-terminal states are scored as a weighted sum of features.
+// TODO: Add more factors (food proximity, opponent threats, center control, etc.)
+pub fn evaluate_sim_state(
+    state: &SimState,
+    our_id: &str,
+    ply: u32,
+    root_opponents: usize,
+    weights: &EvalWeights,
+) -> i32 {
+    let ply = ply as i32;
+    let alive_opponents = state.opponents(our_id).count();
+    let eliminated_opponents = root_opponents.saturating_sub(alive_opponents) as i32;
 
-     let you = match state.snakes.iter().find(|s| s.id == our_id) {
-         Some(s) => s,
-         None => return i32::MIN + 1, // We are dead (use MIN + 1 to distinguish from deeper losses)
-     };
+    let you = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s,
+        None if state.snakes.is_empty() => return DRAW_SCORE + ply, // Everyone is dead
+        None => {
+            // We are dead. Outliving opponents earns a share of the gap up to a draw,
+            // never reaching it (at least one opponent is still alive here).
+            let outlived_credit = (DRAW_SCORE - LOSS_SCORE) / (root_opponents.max(1) as i32) * eliminated_opponents;
+            return LOSS_SCORE + ply + outlived_credit;
+        }
+    };
 
-    // Check for win (only snake left)
-    if state.snakes.len() == 1 {
-        return i32::MAX - 1; // We won (use MAX - 1 to allow depth preference)
+    // Check for win (only snake or squad left). Solo games never "win" this way.
+    if alive_opponents == 0 && root_opponents > 0 {
+        return WIN_SCORE - ply;
     }
 
-    // --- Component Scores --- 
-    let health_score = you.health as i32; // Weight: 1
-    let length_score = you.length() as i32 * 10; // Weight: 10 (length is crucial)
-    
-    // Space Score - Use flood fill from our head in the SimState
-    let space_score = calculate_controlled_space(state, you) as i32 * 2; // Weight: 2
+    let features = extract_features(state, you);
+    let score = score_features(&features, weights);
 
-    // Length Advantage Score - Compare our length to the *longest* opponent
-    let max_opponent_length = state.snakes.iter()
-        .filter(|s| s.id != our_id)
-        .map(|s| s.length())
-        .max()
-        .unwrap_or(0); // If no opponents, advantage is based on 0 length
-    
-    let length_advantage = (you.length() as i32 - max_opponent_length as i32) * 5; // Weight: 5
-
-    // --- Aggregation --- 
-    let score = health_score 
-                + length_score 
-                + space_score 
-                + length_advantage;
-    
-     debug!(
-        "Game Turn {}: Eval for {}: Score={}, (H={}, L={}, S={}, LA={})",
-        state.turn, our_id, score, health_score, length_score, space_score, length_advantage
+    debug!(
+        "Game Turn {}: Eval for {}: Score={} [{}]",
+        state.turn, our_id, score, explain_features(&features, weights)
     );
-    
+
     score
 }
 
+// Named evaluation features. Each is a raw measurement of a position; the weight set decides how much
+// it counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Health,          // Our health
+    Length,          // Our length
+    Space,           // Squares reachable from our head
+    LengthAdvantage, // Our length minus the longest opponent's
+    Territory,       // Our Voronoi cells minus the largest opponent territory
+    Trap,            // Squares we are short of in a pocket an opponent can seal first
+    FoodWon,         // Food we reach first or win head-to-head
+    Hazard,          // Quarters of a hazard our head can expect to sit in (SimState::hazard_quarters)
+    Chokepoints,     // Map chokepoints (board_analysis::analyze_map) we reach first, minus the best opponent's
+}
+
+impl Feature {
+    pub const ALL: [Feature; 9] = [
+        Feature::Health,
+        Feature::Length,
+        Feature::Space,
+        Feature::LengthAdvantage,
+        Feature::Territory,
+        Feature::Trap,
+        Feature::FoodWon,
+        Feature::Hazard,
+        Feature::Chokepoints,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Health => "health",
+            Feature::Length => "length",
+            Feature::Space => "space",
+            Feature::LengthAdvantage => "length_advantage",
+            Feature::Territory => "territory",
+            Feature::Trap => "trap",
+            Feature::FoodWon => "food_won",
+            Feature::Hazard => "hazard",
+            Feature::Chokepoints => "chokepoints",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Feature::ALL.iter().copied().find(|f| f.name() == name)
+    }
+}
+
+// Raw feature values of a position, indexed by `Feature`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeatureVector([i32; Feature::ALL.len()]);
+
+impl FeatureVector {
+    pub fn get(&self, feature: Feature) -> i32 {
+        self.0[feature as usize]
+    }
+
+    fn set(&mut self, feature: Feature, value: i32) {
+        self.0[feature as usize] = value;
+    }
+}
+
+// Runs every feature extractor for snake `you` in a non-terminal state.
+pub fn extract_features(state: &SimState, you: &SimSnake) -> FeatureVector {
+    let mut features = FeatureVector::default();
+    features.set(Feature::Health, you.health as i32);
+    features.set(Feature::Length, you.length() as i32);
+    features.set(Feature::Space, calculate_controlled_space(state, you) as i32);
+    features.set(Feature::LengthAdvantage, length_advantage(state, you));
+    let partition = voronoi::voronoi_partition(state);
+    features.set(Feature::Territory, territory_differential(state, &partition, you));
+    features.set(Feature::Trap, trap_shortfall(state, you));
+    features.set(Feature::FoodWon, food_won(state, you));
+    features.set(Feature::Hazard, you.head().map_or(0, |head| state.hazard_quarters(head) as i32));
+    features.set(Feature::Chokepoints, chokepoint_differential(state, &partition, you));
+    features
+}
+
+// Weighted sum of the features, rounded to the search's integer scores.
+pub fn score_features(features: &FeatureVector, weights: &EvalWeights) -> i32 {
+    Feature::ALL.iter()
+        .map(|&f| features.get(f) as f32 * weights.weight(f))
+        .sum::<f32>()
+        .round() as i32
+}
+
+// Lists each feature's raw value and weighted contribution, e.g. "health=80 (+80)".
+pub fn explain_features(features: &FeatureVector, weights: &EvalWeights) -> String {
+    Feature::ALL.iter()
+        .map(|&f| {
+            let raw = features.get(f);
+            format!("{}={} ({:+})", f.name(), raw, (raw as f32 * weights.weight(f)).round() as i32)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// --- Feature Extractors ---
+
+// Our length compared to the *longest* opponent (0 if there are no opponents).
+fn length_advantage(state: &SimState, you: &SimSnake) -> i32 {
+    let max_opponent_length = state.opponents(&you.id)
+        .map(|s| s.length())
+        .max()
+        .unwrap_or(0);
+    you.length() as i32 - max_opponent_length as i32
+}
+
+// Voronoi cells we reach first vs. the best opponent's.
+fn territory_differential(state: &SimState, partition: &voronoi::VoronoiPartition, you: &SimSnake) -> i32 {
+    let our_territory = partition.territory_of(state, &you.id).cells as i32;
+    let max_opponent_territory = state.opponents(&you.id)
+        .map(|s| partition.territory_of(state, &s.id).cells as i32)
+        .max()
+        .unwrap_or(0);
+    our_territory - max_opponent_territory
+}
+
+// How many squares short of our length the pocket we sit in is, if an opponent can seal it first.
+fn trap_shortfall(state: &SimState, you: &SimSnake) -> i32 {
+    match you.head() {
+        Some(head) => {
+            let analysis = board_analysis::analyze_free_space(state);
+            board_analysis::sealable_pocket(state, &analysis, &you.id, head, 0)
+                .map_or(0, |pocket| (you.length() - pocket) as i32)
+        }
+        None => 0,
+    }
+}
+
+// Chokepoints of the map's safe ground we reach first vs. the best opponent's. Boards without
+// hazards have none to hold.
+fn chokepoint_differential(state: &SimState, partition: &voronoi::VoronoiPartition, you: &SimSnake) -> i32 {
+    if state.hazards.is_empty() {
+        return 0;
+    }
+    let mut held = vec![0i32; state.snakes.len()];
+    for cell in board_analysis::analyze_map(state).articulation_points.keys() {
+        if let Some(&Some(owner)) = partition.owners.get(cell) {
+            held[owner] += 1;
+        }
+    }
+    let held_by = |id: &str| state.snakes.iter().position(|s| s.id == id).map_or(0, |i| held[i]);
+    let best_opponent = state.opponents(&you.id).map(|s| held_by(&s.id)).max().unwrap_or(0);
+    held_by(&you.id) - best_opponent
+}
+
+// Number of food items we reach first or would win a head-to-head for.
+fn food_won(state: &SimState, you: &SimSnake) -> i32 {
+    food_race::analyze_food_races(state, &you.id).iter()
+        .filter(|race| race.claim.is_won())
+        .count() as i32
+}
+
 // Remove old GameState-based evaluation functions
 /*
 pub fn evaluate_state(state: &GameState) -> i32 { ... }
 pub fn evaluate_state_v2(state: &GameState) -> i32 { ... }
-*/ 
\ No newline at end of file
+*/ 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Scores `state` for "me", `ply` moves below a root where it had `root_opponents` opponents.
+    fn score(state: &SimState, ply: u32, root_opponents: usize) -> i32 {
+        evaluate_sim_state(state, "me", ply, root_opponents, &EvalWeights::default())
+    }
+
+    fn board(ids: &[&str]) -> SimState {
+        let snakes = ids.iter().enumerate().map(|(i, id)| SimSnake::new(id, &[(i as i32 * 2, 0)])).collect();
+        SimState::empty(7, 7).with_snakes(snakes)
+    }
+
+    #[test]
+    fn sooner_wins_and_later_losses_score_higher() {
+        let won = board(&["me"]);
+        assert!(score(&won, 1, 1) > score(&won, 3, 1));
+        let lost = board(&["them"]);
+        assert!(score(&lost, 3, 1) > score(&lost, 1, 1));
+    }
+
+    #[test]
+    fn draws_sit_between_losses_and_wins() {
+        let (won, lost, drawn) = (board(&["me"]), board(&["them"]), board(&[]));
+        // Even the slowest win and the latest loss stay on their side of every draw.
+        assert!(score(&drawn, 1, 1) < score(&won, 20, 1));
+        assert!(score(&drawn, 20, 1) > score(&lost, 20, 1));
+        // An undecided position is worth more than any draw and less than any win.
+        let open = board(&["me", "them"]);
+        assert!(score(&drawn, 1, 1) < score(&open, 1, 1) && score(&open, 1, 1) < score(&won, 20, 1));
+    }
+
+    #[test]
+    fn outliving_opponents_earns_credit_short_of_a_draw() {
+        // We die with one of two opponents already gone.
+        let outlived = board(&["them"]);
+        assert!(score(&outlived, 2, 2) > score(&outlived, 2, 1));
+        assert!(score(&outlived, 2, 2) < score(&board(&[]), 2, 2));
+    }
+}
@@ -1,128 +1,120 @@
-use crate::game_state::{Coord, GameState, Move};
+use crate::game_state::{Coord, Move};
 use crate::sim::state::SimState;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 // Evaluates a list of safe moves based on the amount of space reachable
 // from the resulting position.
 // Returns a vector of (Move, space_count) tuples, sorted descending by space_count.
-pub fn evaluate_moves_by_space(game_state: &GameState, safe_moves: &[Move]) -> Vec<(Move, usize)> {
+pub fn evaluate_moves_by_space(sim_state: &SimState, our_id: &str, safe_moves: &[Move]) -> Vec<(Move, usize)> {
     let mut move_scores = Vec::new();
-    let head = &game_state.you.head;
+    let head = match sim_state.snakes.iter().find(|s| s.id == our_id).and_then(|s| s.head()) {
+        Some(h) => h,
+        None => return move_scores,
+    };
 
     for &m in safe_moves {
-        let next_pos = head.apply_move(m);
-        // Check bounds again just in case, though safe_moves should guarantee it
-        if !game_state.board.in_bounds(&next_pos) {
-            continue;
-        }
-        let space = flood_fill(game_state, &next_pos);
+        // Safe moves stay on the board (wrapping around it in wrapped mode)
+        let next_pos = match sim_state.step(head, m) {
+            Some(p) => p,
+            None => continue,
+        };
+        let space = flood_fill_sim(sim_state, our_id, &next_pos, 1);
         move_scores.push((m, space));
     }
 
     // Sort by available space (descending)
-    move_scores.sort_by(|a, b| b.1.cmp(&a.1));
+    move_scores.sort_by_key(|&(_, space)| std::cmp::Reverse(space));
     move_scores
 }
 
-// Performs a flood fill starting from `start` to count accessible empty squares in a GameState.
-// Kept for compatibility if needed elsewhere, but evaluation should use flood_fill_sim.
-pub fn flood_fill(game_state: &GameState, start: &Coord) -> usize {
-    let board = &game_state.board;
-    let mut visited: HashSet<Coord> = HashSet::new();
-    let mut queue: VecDeque<Coord> = VecDeque::new();
-
-    // Create a set of all occupied points (all snake bodies, including self)
-    // For flood fill, we treat our own body as an obstacle too.
-    // Exclude tails as they will move out of the way.
-    let occupied: HashSet<Coord> = board.snakes.iter().flat_map(|snake| {
-        snake.body.iter().enumerate()
-            .filter(|(i, _)| *i < snake.body.len() - 1) // Exclude the very last segment (tail tip)
-            .map(|(_, segment)| *segment)
-    }).collect();
-
-    // Check if the start node itself is valid
-    if !board.in_bounds(start) || occupied.contains(start) {
-        return 0; // Cannot start fill from an invalid or occupied square
-    }
-
-    queue.push_back(*start);
-    visited.insert(*start);
+// Squares blocked by snake bodies on the next turn: every segment except tail tips, which move
+// out of the way unless the rules keep them or the snake just ate. A single-segment snake keeps
+// its head.
+pub fn static_obstacles(sim_state: &SimState) -> HashSet<Coord> {
+    let tails_vacate = sim_state.rules.tails_vacate();
+    sim_state.snakes.iter().flat_map(|snake| {
+        let kept = if tails_vacate && !snake.tail_is_stacked() { snake.body.len().saturating_sub(1).max(1) } else { snake.body.len() };
+        snake.body.iter().take(kept)
+    }).cloned().collect()
+}
 
-    while let Some(p) = queue.pop_front() {
-        // Check all four adjacent cells
-        for neighbor in p.neighbours() {
-            // Skip if out of bounds
-            if !board.in_bounds(&neighbor) {
-                continue;
-            }
+// When each occupied square is vacated, seen from one moving snake.
+// Segment i of a body of length n leaves after n - i turns. Using the first index at which a
+// square appears means a stacked tail (pending growth after eating) stays one turn longer.
+// Under rules where tails never move (constrictor), nothing is ever vacated. Squadmates the mover
+// can pass through are left out.
+pub struct Vacancy {
+    vacate: HashMap<Coord, (u32, usize)>, // Turns until free, and the index of the occupying snake
+    mover: Option<usize>,
+}
 
-            // Skip if already visited
-            if visited.contains(&neighbor) {
+impl Vacancy {
+    pub fn new(sim_state: &SimState, snake_id: &str) -> Self {
+        let mut vacate: HashMap<Coord, (u32, usize)> = HashMap::new();
+        let tails_vacate = sim_state.rules.tails_vacate();
+        let mover = sim_state.snakes.iter().find(|s| s.id == snake_id);
+        for (snake_index, snake) in sim_state.snakes.iter().enumerate() {
+            if mover.is_some_and(|m| sim_state.can_pass_through(m, snake)) {
                 continue;
             }
-
-            // Skip if occupied by a snake body (excluding tails)
-            if occupied.contains(&neighbor) {
-                continue;
+            let n = snake.body.len() as u32;
+            for (i, segment) in snake.body.iter().enumerate() {
+                let turns = if tails_vacate { n - i as u32 } else { u32::MAX / 2 };
+                let entry = vacate.entry(*segment).or_insert((turns, snake_index));
+                if turns > entry.0 {
+                    *entry = (turns, snake_index);
+                }
             }
-
-            // Skip hazards (optional, could add later)
-            // if board.hazards.contains(&neighbor) {
-            //     continue;
-            // }
-
-            // Mark as visited and add to queue
-            visited.insert(neighbor);
-            queue.push_back(neighbor);
+        }
+        Vacancy {
+            vacate,
+            mover: sim_state.snakes.iter().position(|s| s.id == snake_id),
         }
     }
 
-    // Return the number of accessible cells (size of the visited set)
-    visited.len()
+    // Whether `coord` is free when the mover arrives `turn` turns from now having eaten `eaten`
+    // food on the way. Each food eaten holds the mover's own segments one turn longer.
+    pub fn is_free(&self, coord: &Coord, turn: u32, eaten: u32) -> bool {
+        match self.vacate.get(coord) {
+            Some(&(turns, owner)) if Some(owner) == self.mover => turn >= turns + eaten,
+            Some(&(turns, _)) => turn >= turns,
+            None => true,
+        }
+    }
 }
 
-// Performs a flood fill starting from `start` to count accessible empty squares in a SimState.
-pub fn flood_fill_sim(sim_state: &SimState, start: &Coord) -> usize {
-    let mut visited: HashSet<Coord> = HashSet::new();
-    let mut queue: VecDeque<Coord> = VecDeque::new();
-
-    // Create a set of occupied points from SimSnakes (excluding tails)
-    let occupied: HashSet<Coord> = sim_state.snakes.iter().flat_map(|snake| {
-        snake.body.iter().skip(1) // Skip head, only block body segments (tail is implicitly not included as we pop it)
-            // Corrected logic: Exclude the *last* element (tail tip), not skip(1)
-            // .enumerate()
-            // .filter(|(i, _)| *i < snake.body.len() - 1) // Old logic, might be wrong for VecDeque
-            // .map(|(_, segment)| *segment)
-
-            // Let's iterate excluding the tail if it exists
-            .take(if snake.body.len() > 1 { snake.body.len() - 1 } else { 0 })
-
-    }).cloned().collect();
-
+// Performs a time-aware flood fill in a SimState, counting the squares snake `snake_id` can reach
+// from `start`, which it occupies `start_turn` turns from now (0 when `start` is its own head).
+// A body segment only blocks us if we would arrive before it is vacated. Food eaten along the way
+// grows our own body, so our own segments are held for one extra turn per food.
+pub fn flood_fill_sim(sim_state: &SimState, snake_id: &str, start: &Coord, start_turn: u32) -> usize {
+    let vacancy = Vacancy::new(sim_state, snake_id);
+    let is_free = |coord: &Coord, turn: u32, eaten: u32| vacancy.is_free(coord, turn, eaten);
 
-    // Check if the start node itself is valid
-    if !sim_state.in_bounds(start) || occupied.contains(start) {
+    // Check if the start node itself is valid (our own head is always a valid start)
+    if !sim_state.in_bounds(start) || (start_turn > 0 && !is_free(start, start_turn, 0)) {
         return 0; // Cannot start fill from an invalid or occupied square
     }
 
-    queue.push_back(*start);
-    visited.insert(*start);
+    let mut visited: HashSet<Coord> = HashSet::new();
+    let mut queue: VecDeque<(Coord, u32, u32)> = VecDeque::new(); // (square, arrival turn, food eaten)
 
-    while let Some(p) = queue.pop_front() {
-        // Check all four adjacent cells
-        for neighbor in p.neighbours() {
-            // Skip if out of bounds
-            if !sim_state.in_bounds(&neighbor) {
-                continue;
-            }
+    let start_eaten = if start_turn > 0 && sim_state.food.contains(start) { 1 } else { 0 };
+    queue.push_back((*start, start_turn, start_eaten));
+    visited.insert(*start);
 
+    while let Some((p, turn, eaten)) = queue.pop_front() {
+        let next_turn = turn + 1;
+        // Check all adjacent cells on the board
+        for neighbor in sim_state.neighbours(&p) {
             // Skip if already visited
             if visited.contains(&neighbor) {
                 continue;
             }
 
-            // Skip if occupied by a snake body (excluding tails)
-            if occupied.contains(&neighbor) {
+            // Skip if still occupied when we would get there. Not marked visited, so a
+            // longer path arriving after the segment has moved on can still claim it.
+            if !is_free(&neighbor, next_turn, eaten) {
                 continue;
             }
 
@@ -130,11 +122,53 @@ pub fn flood_fill_sim(sim_state: &SimState, start: &Coord) -> usize {
             // if sim_state.hazards.contains(&neighbor) { continue; }
 
             // Mark as visited and add to queue
+            let next_eaten = eaten + sim_state.food.contains(&neighbor) as u32;
             visited.insert(neighbor);
-            queue.push_back(neighbor);
+            queue.push_back((neighbor, next_turn, next_eaten));
         }
     }
 
     // Return the number of accessible cells (size of the visited set)
     visited.len()
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    // A 3x2 board we fill except for (1, 1), our head at (0, 1) and our tail tip stacked on (2, 1)
+    // from eating last turn.
+    fn coiled() -> SimState {
+        SimState::empty(3, 2).with_snakes(vec![SimSnake::new("me", &[(0, 1), (0, 0), (1, 0), (2, 0), (2, 1), (2, 1)])])
+    }
+
+    fn space(state: &SimState, id: &str) -> usize {
+        let head = *state.snakes.iter().find(|s| s.id == id).and_then(|s| s.head()).unwrap();
+        flood_fill_sim(state, id, &head, 0)
+    }
+
+    #[test]
+    fn squares_count_once_their_segment_has_moved_on() {
+        // Following our own tail round reaches every square of the board.
+        assert_eq!(space(&coiled(), "me"), 6);
+
+        // An opponent's tail end leaves ahead of us; its head end does not.
+        let corridor = |them: &[(i32, i32)]| {
+            SimState::empty(6, 1).with_snakes(vec![SimSnake::new("me", &[(0, 0)]), SimSnake::new("them", them)])
+        };
+        assert_eq!(space(&corridor(&[(5, 0), (4, 0), (3, 0), (2, 0)]), "me"), 6);
+        assert_eq!(space(&corridor(&[(2, 0), (3, 0), (4, 0), (5, 0)]), "me"), 2);
+    }
+
+    #[test]
+    fn food_on_the_way_holds_our_tail_back() {
+        // Eating at (1, 1) keeps the stacked tail on (2, 1) for the turn we would reach it.
+        assert_eq!(space(&coiled().with_food(&[(1, 1)]), "me"), 2);
+        // Food only holds back our own body, not the opponents'.
+        let state = SimState::empty(6, 1)
+            .with_snakes(vec![SimSnake::new("me", &[(0, 0)]), SimSnake::new("them", &[(5, 0), (4, 0), (3, 0), (2, 0)])])
+            .with_food(&[(1, 0)]);
+        assert_eq!(space(&state, "me"), 6);
+    }
+}
@@ -1,73 +1,56 @@
-use crate::game_state::{Coord, GameState, Move};
+use crate::game_state::{Coord, Move};
+use crate::sim::state::SimState;
+use super::food_race;
+use super::food_safety::{self, FoodSafety};
+use super::pathfinding;
 use log::debug;
-
-// Default health threshold below which the snake will seek food.
-const DEFAULT_FOOD_THRESHOLD: u32 = 50;
-
-// Determines if the snake should actively seek food based on health.
-pub fn should_seek_food(game_state: &GameState) -> bool {
-    let threshold = std::env::var("FOOD_THRESHOLD")
-        .ok()
-        .and_then(|s| s.parse::<u32>().ok())
-        .unwrap_or(DEFAULT_FOOD_THRESHOLD);
-
-    let should_seek = game_state.you.health < threshold;
-    if should_seek {
-        debug!(
-            "Game {} Turn {}: Health ({}) below threshold ({}), seeking food.",
-            game_state.game.id,
-            game_state.turn,
-            game_state.you.health,
-            threshold
-        );
-    }
-    should_seek
-}
-
-// Finds the safe move that leads closest to the nearest food item.
-// Returns None if no food exists or no safe moves are provided.
-pub fn find_move_to_closest_food(game_state: &GameState, safe_moves: &[Move]) -> Option<Move> {
-    if game_state.board.food.is_empty() || safe_moves.is_empty() {
+use std::cmp::Reverse;
+
+// Finds the safe move that starts the healthiest path to the best food item, routing around walls,
+// bodies and hazards. Only food we can reach without starving is considered. Food that would
+// leave us in a dead end after eating goes last; otherwise food we reach first is preferred, then
+// food we win head-to-head, then the rest (see FoodClaim::rank), and within a claim the safest
+// food (see food_safety) wins, ties going to the nearest. Returns None if no food exists, no safe
+// moves are provided or no food is reachable in time.
+pub fn find_move_to_closest_food(sim_state: &SimState, our_id: &str, safe_moves: &[Move]) -> Option<Move> {
+    if sim_state.food.is_empty() || safe_moves.is_empty() {
         return None;
     }
-
-    let head = &game_state.you.head;
-
-    // Find the coordinates of the closest food item
-    let closest_food_coord = game_state.board.food.iter()
-        .min_by_key(|food_coord| manhattan_distance(head, food_coord))
-        .cloned(); // Clone the Option<Coord>
-
-    // If no food found (should not happen if board.food wasn't empty, but check anyway)
-    let target_food = match closest_food_coord {
-        Some(coord) => coord,
-        None => return None,
+    let you = sim_state.snakes.iter().find(|s| s.id == our_id)?;
+    let head = you.head()?;
+    let our_length = you.length();
+
+    let mut candidates: Vec<(food_race::FoodRace, Vec<Coord>, FoodSafety)> = food_race::analyze_food_races(sim_state, our_id)
+        .into_iter()
+        .filter_map(|race| {
+            // None when only reachable through a move we consider unsafe, or we would starve first
+            let path = pathfinding::healthiest_path(sim_state, our_id, &race.food, safe_moves)?.path;
+            let safety = food_safety::score_food_safety(sim_state, our_id, &race.food, &path)?;
+            Some((race, path, safety))
+        })
+        .collect();
+    candidates.sort_by_key(|(race, path, safety)| {
+        (safety.is_dead_end(our_length), race.claim.rank(), Reverse(safety.score), path.len())
+    });
+
+    let (race, path, safety) = match candidates.first() {
+        Some(c) => c,
+        None => {
+            debug!("Turn {}: No food reachable without starving.", sim_state.turn);
+            return None;
+        }
     };
     debug!(
-        "Game {} Turn {}: Closest food at ({}, {}).",
-        game_state.game.id, game_state.turn, target_food.x, target_food.y
+        "Turn {}: Targeting food at ({}, {}), {} moves away ({:?}, safety {:?}).",
+        sim_state.turn, race.food.x, race.food.y, path.len(), race.claim, safety
     );
 
-
-    // Find which safe move gets us closest (minimum Manhattan distance) to the target food
-    let best_move = safe_moves.iter()
-        .min_by_key(|&&m| {
-            let next_pos = head.apply_move(m);
-            manhattan_distance(&next_pos, &target_food)
-        })
-        .cloned(); // Clone the Option<Move>
-
+    let best_move = pathfinding::first_move(sim_state, head, path);
     if let Some(chosen_move) = best_move {
          debug!(
-            "Game {} Turn {}: Chose move {} towards food.",
-            game_state.game.id, game_state.turn, chosen_move.as_str()
+            "Turn {}: Chose move {} towards food.",
+            sim_state.turn, chosen_move.as_str()
         );
     }
-
     best_move
 }
-
-// Calculates the Manhattan distance between two coordinates.
-fn manhattan_distance(a: &Coord, b: &Coord) -> u32 {
-    ((a.x - b.x).abs() + (a.y - b.y).abs()) as u32
-} 
\ No newline at end of file
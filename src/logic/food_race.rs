@@ -0,0 +1,176 @@
+use crate::game_state::Coord;
+use crate::sim::state::SimState;
+use super::pathfinding;
+use std::collections::{HashMap, HashSet};
+
+// Likely result of meeting an opponent head-on at a food we both reach on the same turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadToHeadOutcome {
+    Win,     // We are longer than every opponent arriving with us
+    BothDie, // The longest opponent arriving with us is our length
+    Loss,    // An opponent arriving with us is longer
+}
+
+// Who gets a food item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoodClaim {
+    Ours,                         // We arrive strictly first
+    Contested(HeadToHeadOutcome), // We arrive together with the fastest opponent(s)
+    Lost,                         // An opponent arrives first, or we cannot reach it at all
+}
+
+impl FoodClaim {
+    // Preference order when choosing food to go for: lower is better. Lost food only wastes turns,
+    // so it ranks above a head-to-head we do not win.
+    pub fn rank(&self) -> u8 {
+        match self {
+            FoodClaim::Ours => 0,
+            FoodClaim::Contested(HeadToHeadOutcome::Win) => 1,
+            FoodClaim::Lost => 2,
+            FoodClaim::Contested(HeadToHeadOutcome::BothDie) => 3,
+            FoodClaim::Contested(HeadToHeadOutcome::Loss) => 4,
+        }
+    }
+
+    // Food we can expect to eat.
+    pub fn is_won(&self) -> bool {
+        self.rank() <= 1
+    }
+}
+
+// One snake's path distance to a food item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeReach {
+    pub snake_id: String,
+    pub distance: Option<u32>, // None when the snake cannot reach the food
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoodRace {
+    pub food: Coord,
+    pub reaches: Vec<SnakeReach>, // One entry per snake, in `SimState::snakes` order
+    pub claim: FoodClaim,         // From the perspective of the snake the analysis was run for
+}
+
+impl FoodRace {
+    pub fn our_distance(&self, our_id: &str) -> Option<u32> {
+        self.reaches.iter().find(|r| r.snake_id == our_id).and_then(|r| r.distance)
+    }
+}
+
+// Works out, for every food on the board, how far each snake is from it along real paths and
+// whether snake `our_id` wins it, has to contest it head-to-head, or loses it.
+pub fn analyze_food_races(state: &SimState, our_id: &str) -> Vec<FoodRace> {
+    let distance_maps: HashMap<&str, HashMap<Coord, u32>> = state.snakes.iter()
+        .map(|s| (s.id.as_str(), pathfinding::distance_map(state, &s.id)))
+        .collect();
+    let our_length = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s.length(),
+        None => return Vec::new(),
+    };
+    // Squadmates share food with us rather than race us for it.
+    let rivals: HashSet<&str> = state.opponents(our_id).map(|s| s.id.as_str()).collect();
+
+    state.food.iter().map(|food| {
+        let reaches: Vec<SnakeReach> = state.snakes.iter().map(|s| SnakeReach {
+            snake_id: s.id.clone(),
+            distance: distance_maps[s.id.as_str()].get(food).copied(),
+            length: s.length(),
+        }).collect();
+
+        let our_distance = reaches.iter().find(|r| r.snake_id == our_id).and_then(|r| r.distance);
+        let fastest_opponent = reaches.iter()
+            .filter(|r| rivals.contains(r.snake_id.as_str()))
+            .filter_map(|r| r.distance)
+            .min();
+
+        let claim = match (our_distance, fastest_opponent) {
+            (None, _) => FoodClaim::Lost,
+            (Some(_), None) => FoodClaim::Ours,
+            (Some(ours), Some(theirs)) if ours < theirs => FoodClaim::Ours,
+            (Some(ours), Some(theirs)) if ours > theirs => FoodClaim::Lost,
+            (Some(ours), Some(_)) => {
+                let longest_rival = reaches.iter()
+                    .filter(|r| rivals.contains(r.snake_id.as_str()) && r.distance == Some(ours))
+                    .map(|r| r.length)
+                    .max()
+                    .unwrap_or(0);
+                let outcome = match our_length.cmp(&longest_rival) {
+                    std::cmp::Ordering::Greater => HeadToHeadOutcome::Win,
+                    std::cmp::Ordering::Equal => HeadToHeadOutcome::BothDie,
+                    std::cmp::Ordering::Less => HeadToHeadOutcome::Loss,
+                };
+                FoodClaim::Contested(outcome)
+            }
+        };
+
+        FoodRace { food: *food, reaches, claim }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    // A 9x1 corridor: we face right from the left end, `them` faces left from the right end.
+    fn corridor(them: &[(i32, i32)], food: &[(i32, i32)]) -> SimState {
+        SimState::empty(9, 1)
+            .with_snakes(vec![SimSnake::new("me", &[(2, 0), (1, 0), (0, 0)]), SimSnake::new("them", them)])
+            .with_food(food)
+    }
+
+    fn claim(state: &SimState, food: (i32, i32)) -> FoodClaim {
+        let races = analyze_food_races(state, "me");
+        races.into_iter().find(|r| r.food == Coord { x: food.0, y: food.1 }).unwrap().claim
+    }
+
+    #[test]
+    fn nearer_snakes_win_food() {
+        let state = corridor(&[(6, 0), (7, 0), (8, 0)], &[(3, 0), (5, 0)]);
+        assert_eq!(claim(&state, (3, 0)), FoodClaim::Ours);
+        assert_eq!(claim(&state, (5, 0)), FoodClaim::Lost);
+
+        let race = analyze_food_races(&state, "me").into_iter().find(|r| r.food == Coord { x: 5, y: 0 }).unwrap();
+        assert_eq!(race.our_distance("me"), Some(3));
+        assert_eq!(race.reaches[1], SnakeReach { snake_id: "them".to_string(), distance: Some(1), length: 3 });
+    }
+
+    #[test]
+    fn ties_go_to_the_head_to_head() {
+        // Both arrive at (4, 0) on turn 2; the outcome depends on length.
+        let shorter = corridor(&[(6, 0), (7, 0)], &[(4, 0)]);
+        assert_eq!(claim(&shorter, (4, 0)), FoodClaim::Contested(HeadToHeadOutcome::Win));
+        let equal = corridor(&[(6, 0), (7, 0), (8, 0)], &[(4, 0)]);
+        assert_eq!(claim(&equal, (4, 0)), FoodClaim::Contested(HeadToHeadOutcome::BothDie));
+        let longer = corridor(&[(6, 0), (7, 0), (8, 0), (8, 0)], &[(4, 0)]);
+        assert_eq!(claim(&longer, (4, 0)), FoodClaim::Contested(HeadToHeadOutcome::Loss));
+    }
+
+    #[test]
+    fn squadmates_do_not_race_us_for_food() {
+        // The mate is nearer, but only opponents count as rivals.
+        let state = SimState::empty(9, 1)
+            .with_ruleset("squad")
+            .with_snakes(vec![
+                SimSnake::new("me", &[(2, 0), (1, 0), (0, 0)]).with_squad("red"),
+                SimSnake::new("mate", &[(6, 0), (7, 0), (8, 0)]).with_squad("red"),
+            ])
+            .with_food(&[(5, 0)]);
+        assert_eq!(claim(&state, (5, 0)), FoodClaim::Ours);
+    }
+
+    #[test]
+    fn won_food_ranks_first_and_losing_fights_last() {
+        let claims = [
+            FoodClaim::Ours,
+            FoodClaim::Contested(HeadToHeadOutcome::Win),
+            FoodClaim::Lost,
+            FoodClaim::Contested(HeadToHeadOutcome::BothDie),
+            FoodClaim::Contested(HeadToHeadOutcome::Loss),
+        ];
+        assert!(claims.windows(2).all(|pair| pair[0].rank() < pair[1].rank()));
+        assert_eq!(claims.iter().filter(|c| c.is_won()).count(), 2);
+    }
+}
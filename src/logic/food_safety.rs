@@ -0,0 +1,117 @@
+use crate::game_state::Coord;
+use crate::sim::state::SimState;
+use super::flood_fill;
+use std::collections::VecDeque;
+
+// Weights of the food safety score.
+const SPACE_WEIGHT: i32 = 2;        // Per square reachable after eating, up to twice our new length
+const EXIT_WEIGHT: i32 = 5;         // Per free neighbour of the food besides the one we arrive from
+const HAZARD_PENALTY: i32 = 20;     // Food sitting in a hazard
+const ENEMY_HEAD_PENALTY: i32 = 15; // Per opponent at least our length with its head close to the food
+const ENEMY_HEAD_RADIUS: u32 = 2;
+
+// How safe it is to eat one food item by a given path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoodSafety {
+    pub food: Coord,
+    pub space_after: usize,        // Squares reachable from the food with our body one longer
+    pub exits: usize,              // Free neighbours of the food other than the square we come from
+    pub in_hazard: bool,
+    pub enemy_heads_nearby: usize, // Opponents at least our length within ENEMY_HEAD_RADIUS of the food
+    pub score: i32,
+}
+
+impl FoodSafety {
+    // Eating here leaves us less room than our own (longer) body needs, or no way out.
+    pub fn is_dead_end(&self, our_length: usize) -> bool {
+        self.space_after < our_length + 1 || self.exits == 0
+    }
+}
+
+// Scores eating `food` for snake `our_id`, which gets there along `path` (squares after its head,
+// ending at the food). Space is measured in the position right after eating: our body follows the
+// path and grows by one.
+pub fn score_food_safety(state: &SimState, our_id: &str, food: &Coord, path: &[Coord]) -> Option<FoodSafety> {
+    let you = state.snakes.iter().find(|s| s.id == our_id)?;
+    let new_length = you.length() + 1;
+
+    // Our body once we have eaten: the path (newest first) followed by what is left of the old body.
+    let mut after = state.clone();
+    let body: VecDeque<Coord> = path.iter().rev()
+        .chain(you.body.iter())
+        .take(new_length)
+        .cloned()
+        .collect();
+    if let Some(snake) = after.snakes.iter_mut().find(|s| s.id == our_id) {
+        snake.body = body;
+    }
+    after.food.remove(food);
+    let space_after = flood_fill::flood_fill_sim(&after, our_id, food, 0);
+
+    let obstacles = flood_fill::static_obstacles(&after);
+    let arrived_from = path.iter().rev().nth(1).or(you.head());
+    let exits = state.neighbours(food)
+        .filter(|n| Some(n) != arrived_from && !obstacles.contains(n))
+        .count();
+
+    let in_hazard = state.hazards.contains(food);
+    let enemy_heads_nearby = state.opponents(our_id)
+        .filter(|s| s.length() >= you.length())
+        .filter_map(|s| s.head())
+        .filter(|h| h.manhattan_distance(food) <= ENEMY_HEAD_RADIUS)
+        .count();
+
+    let score = space_after.min(2 * new_length) as i32 * SPACE_WEIGHT
+        + exits as i32 * EXIT_WEIGHT
+        - if in_hazard { HAZARD_PENALTY } else { 0 }
+        - enemy_heads_nearby as i32 * ENEMY_HEAD_PENALTY;
+
+    Some(FoodSafety { food: *food, space_after, exits, in_hazard, enemy_heads_nearby, score })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Move;
+    use crate::logic::pathfinding;
+    use crate::sim::state::SimSnake;
+
+    // We head up the left edge from (0, 2). "wall" lies up x = 1 from (1, 3), its head at the top,
+    // making (0, 3)-(0, 6) a dead end.
+    fn board() -> SimState {
+        SimState::empty(7, 7)
+            .with_snakes(vec![
+                SimSnake::new("me", &[(0, 2), (0, 1), (0, 0)]),
+                SimSnake::new("wall", &[(1, 6), (1, 5), (1, 4), (1, 3)]),
+            ])
+            .with_food(&[(0, 6), (3, 2)])
+    }
+
+    fn safety(state: &SimState, x: i32, y: i32) -> FoodSafety {
+        let food = Coord { x, y };
+        let all_moves = [Move::Up, Move::Down, Move::Left, Move::Right];
+        let path = pathfinding::shortest_path(state, "me", &food, &all_moves).unwrap();
+        score_food_safety(state, "me", &food, &path).unwrap()
+    }
+
+    #[test]
+    fn food_at_the_end_of_a_dead_end_is_unsafe() {
+        let state = board();
+        let trapped = safety(&state, 0, 6);
+        assert_eq!((trapped.space_after, trapped.exits, trapped.enemy_heads_nearby), (1, 0, 1));
+        assert!(trapped.is_dead_end(3));
+
+        let open = safety(&state, 3, 2);
+        assert_eq!((open.exits, open.enemy_heads_nearby), (3, 0));
+        assert!(!open.is_dead_end(3));
+        assert!(open.score > trapped.score);
+    }
+
+    #[test]
+    fn food_in_a_hazard_costs_the_hazard_penalty() {
+        let state = board();
+        let hazardous = board().with_hazards(&[(3, 2)]);
+        assert!(safety(&hazardous, 3, 2).in_hazard);
+        assert_eq!(safety(&hazardous, 3, 2).score, safety(&state, 3, 2).score - HAZARD_PENALTY);
+    }
+}
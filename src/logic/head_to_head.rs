@@ -1,84 +1,157 @@
-use crate::game_state::{Coord, GameState, Move};
-use log::debug;
+use crate::game_state::Coord;
+use crate::sim::state::SimState;
+use super::flood_fill;
+use super::safe_move::legal_moves;
+use std::collections::HashMap;
 
-// Checks if a potential move `target` leads to a dangerous head-to-head collision.
-// Returns true if it's dangerous, false otherwise.
-pub fn is_dangerous_head_to_head(
-    state: &GameState,
-    target: &Coord,
-) -> bool {
-    let my_id = &state.you.id;
-    let my_length = state.you.length;
+// How likely a move is to end in a head-to-head we lose or tie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskTier {
+    Safe,     // No opponent at least our length can move there
+    Unlikely, // Opponents that could have clearly better options
+    Possible,
+    Likely,
+    Certain,  // An opponent at least our length has no other safe move
+}
 
-    for snake in &state.board.snakes {
-        // Skip self
-        if snake.id == *my_id {
-            continue;
+impl RiskTier {
+    fn from_probability(probability: f32) -> Self {
+        if probability <= 0.0 {
+            RiskTier::Safe
+        } else if probability >= 1.0 {
+            RiskTier::Certain
+        } else if probability < 1.0 / 3.0 {
+            RiskTier::Unlikely
+        } else if probability < 2.0 / 3.0 {
+            RiskTier::Possible
+        } else {
+            RiskTier::Likely
         }
+    }
+}
 
-        // Skip if snake is shorter or dead (length 0? Check API spec)
-        if snake.length == 0 { continue; }
+// Head-to-head risk of moving onto one square.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadToHeadRisk {
+    pub probability: f32, // Chance that at least one opponent at least our length moves there too
+    pub tier: RiskTier,
+}
 
-        // Potential head collision square for the opponent
-        let their_head = &snake.head;
+impl HeadToHeadRisk {
+    pub const NONE: HeadToHeadRisk = HeadToHeadRisk { probability: 0.0, tier: RiskTier::Safe };
+}
 
-        // Check if any of the opponent's possible next moves land on our target square
-        for &direction in &[Move::Up, Move::Down, Move::Left, Move::Right] {
-            let their_potential_target = their_head.apply_move(direction);
+// Chance of each square an opponent can move to, from its own safe moves. Options are weighted by
+// the space the opponent would have there, so a cramped square next to a roomy one is unlikely.
+pub fn opponent_move_probabilities(state: &SimState, snake_id: &str) -> HashMap<Coord, f32> {
+    let head = match state.snakes.iter().find(|s| s.id == snake_id).and_then(|s| s.head()) {
+        Some(h) => *h,
+        None => return HashMap::new(),
+    };
+    let options: Vec<(Coord, f32)> = legal_moves(state, snake_id).into_iter()
+        .filter_map(|m| state.step(&head, m))
+        .map(|target| {
+            (target, flood_fill::flood_fill_sim(state, snake_id, &target, 1).max(1) as f32)
+        })
+        .collect();
+    let total: f32 = options.iter().map(|(_, space)| space).sum();
+    options.into_iter().map(|(target, space)| (target, space / total)).collect()
+}
 
-            // Is this opponent move valid (within bounds)?
-            if !state.board.in_bounds(&their_potential_target) {
-                continue;
-            }
+// Head-to-head risk for snake `our_id` of every square next to an opponent's head. Only opponents
+// at least our length count, and only through moves they can actually make. Squadmates count too:
+// a head-on collision eliminates them like anyone else. Squares missing from the map carry no risk.
+pub fn head_to_head_risks(state: &SimState, our_id: &str) -> HashMap<Coord, HeadToHeadRisk> {
+    let our_length = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s.length(),
+        None => return HashMap::new(),
+    };
 
-            // If opponent could move to our target square *and* they are longer or equal length...
-            if their_potential_target == *target && snake.length >= my_length {
-                debug!(
-                    "Game {} Turn {}: Potential head-to-head collision at ({},{}) with snake {} (length {} vs our {}). Avoiding.",
-                    state.game.id,
-                    state.turn,
-                    target.x,
-                    target.y,
-                    snake.id,
-                    snake.length,
-                    my_length
-                );
-                return true; // Dangerous collision detected
-            }
+    // Probability that no dangerous opponent arrives, per square.
+    let mut clear: HashMap<Coord, f32> = HashMap::new();
+    for snake in state.snakes.iter().filter(|s| s.id != our_id && s.length() >= our_length) {
+        for (target, probability) in opponent_move_probabilities(state, &snake.id) {
+            *clear.entry(target).or_insert(1.0) *= 1.0 - probability;
         }
     }
 
-    false // No dangerous head-to-head detected for this target square
+    clear.into_iter()
+        .map(|(target, clear)| {
+            let probability = 1.0 - clear;
+            (target, HeadToHeadRisk { probability, tier: RiskTier::from_probability(probability) })
+        })
+        .collect()
 }
 
-// Basic heuristic to prefer head-to-head if we are longer.
-// Note: This is aggressive and doesn't check if the opponent *can* actually move there safely.
-// Use with caution, maybe combine with lookahead later.
-pub fn is_advantageous_head_to_head(
-    state: &GameState,
-    target: &Coord,
-) -> bool {
-     let my_id = &state.you.id;
-     let my_length = state.you.length;
+// Chance that a strictly shorter opponent moves onto `target` too, so that snake `our_id` wins the
+// head-to-head there. Only counts moves the opponents can actually make (see
+// opponent_move_probabilities).
+pub fn advantageous_head_to_head(state: &SimState, our_id: &str, target: &Coord) -> f32 {
+    let our_length = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s.length(),
+        None => return 0.0,
+    };
+    let mut clear = 1.0;
+    let adjacent_shorter = state.opponents(our_id).filter(|s| {
+        s.length() < our_length && s.head().is_some_and(|h| h.manhattan_distance(target) == 1)
+    });
+    for snake in adjacent_shorter {
+        if let Some(probability) = opponent_move_probabilities(state, &snake.id).get(target) {
+            clear *= 1.0 - probability;
+        }
+    }
+    1.0 - clear
+}
 
-     for snake in &state.board.snakes {
-        if snake.id == *my_id || snake.length == 0 { continue; }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
 
-        let their_head = &snake.head;
-        for &direction in &[Move::Up, Move::Down, Move::Left, Move::Right] {
-            let their_potential_target = their_head.apply_move(direction);
-            if !state.board.in_bounds(&their_potential_target) {
-                continue;
-            }
-            // If opponent could move to our target and we are STRICTLY longer
-            if their_potential_target == *target && my_length > snake.length {
-                 debug!(
-                    "Game {} Turn {}: Potential ADVANTAGEOUS head-to-head at ({},{}) with snake {} (length {} vs our {}). Considering.",
-                    state.game.id, state.turn, target.x, target.y, snake.id, snake.length, my_length
-                );
-                return true;
-            }
+    fn board(snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(7, 7).with_snakes(snakes)
+    }
+
+    fn tier(risks: &HashMap<Coord, HeadToHeadRisk>, x: i32, y: i32) -> RiskTier {
+        risks.get(&Coord { x, y }).map_or(RiskTier::Safe, |r| r.tier)
+    }
+
+    #[test]
+    fn an_opponent_with_one_way_out_is_certain_to_take_it() {
+        // Cornered at (0, 6) with its neck to the right, "them" can only go down.
+        let state = board(vec![
+            SimSnake::new("me", &[(1, 4), (2, 4), (3, 4)]),
+            SimSnake::new("them", &[(0, 6), (1, 6), (1, 5)]),
+        ]);
+        let risks = head_to_head_risks(&state, "me");
+        assert_eq!(tier(&risks, 0, 5), RiskTier::Certain);
+        // Squares it cannot reach carry no risk, even next to its head.
+        assert_eq!(tier(&risks, 1, 6), RiskTier::Safe);
+        assert_eq!(risks.len(), 1);
+    }
+
+    #[test]
+    fn an_opponent_with_two_equal_options_splits_the_risk() {
+        let state = board(vec![
+            SimSnake::new("me", &[(3, 3), (3, 2), (3, 1)]),
+            SimSnake::new("them", &[(3, 6), (3, 5), (3, 4)]),
+        ]);
+        let risks = head_to_head_risks(&state, "me");
+        for x in [2, 4] {
+            let risk = risks[&Coord { x, y: 6 }];
+            assert!((risk.probability - 0.5).abs() < 1e-6);
+            assert_eq!(risk.tier, RiskTier::Possible);
         }
-     }
-     false
-} 
\ No newline at end of file
+    }
+
+    #[test]
+    fn shorter_opponents_are_targets_not_threats() {
+        let state = board(vec![
+            SimSnake::new("me", &[(1, 4), (2, 4), (3, 4), (4, 4)]),
+            SimSnake::new("them", &[(0, 6), (1, 6), (1, 5)]),
+        ]);
+        assert!(head_to_head_risks(&state, "me").is_empty());
+        assert_eq!(advantageous_head_to_head(&state, "me", &Coord { x: 0, y: 5 }), 1.0);
+        assert_eq!(advantageous_head_to_head(&state, "me", &Coord { x: 1, y: 3 }), 0.0);
+    }
+}
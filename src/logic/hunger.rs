@@ -0,0 +1,182 @@
+use crate::game_state::Move;
+use crate::sim::state::SimState;
+use super::pathfinding;
+use log::debug;
+
+// Default health at which hunger starts. `FOOD_THRESHOLD` overrides it for every ruleset.
+pub const DEFAULT_SEEK_HEALTH: u32 = 50;
+const SEEK_HEALTH_ENV: &str = "FOOD_THRESHOLD";
+
+// Urgency from which food takes priority. Health alone reaches it at three quarters of
+// seek_health left at the food; a length gap alone once an opponent is two segments longer.
+const SEEK_URGENCY: f32 = 0.25;
+
+const LENGTH_DEFICIT_STEP: f32 = 0.1; // Extra urgency per segment the longest opponent has on us
+const MAX_LENGTH_URGENCY: f32 = 0.5;
+const LEAD_RELIEF: f32 = 0.5;         // Urgency is scaled by this once we lead comfortably...
+const COMFORTABLE_LEAD: usize = 3;
+const CRITICAL_SLACK: u32 = 10;       // ...unless we would arrive at food with this little health left
+
+// How hungry a snake is, worked out from its health, the health it needs to reach the nearest
+// food and its length compared with its opponents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hunger {
+    pub urgency: f32,                // 0 (not hungry) to 1 (starving)
+    pub food_distance: Option<u32>,  // Moves to the nearest reachable food
+    pub health_at_food: Option<u32>, // Health left on arriving there, after hazard damage
+}
+
+impl Hunger {
+    // Whether food should take priority over other considerations. Milder hunger only shifts the
+    // evaluation weights.
+    pub fn should_seek_food(&self) -> bool {
+        self.urgency >= SEEK_URGENCY
+    }
+}
+
+// Per-ruleset hunger settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HungerPolicy {
+    pub seek_health: u32, // Health left at the nearest food below which we get hungry; 0 disables hunger
+}
+
+impl Default for HungerPolicy {
+    fn default() -> Self {
+        HungerPolicy { seek_health: DEFAULT_SEEK_HEALTH }
+    }
+}
+
+impl HungerPolicy {
+    pub fn for_ruleset(ruleset: &str) -> Self {
+        let seek_health = std::env::var(SEEK_HEALTH_ENV)
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(match ruleset {
+                "constrictor" => 0, // Health never drops and snakes grow anyway
+                "royale" => 60,      // Hazards drain health fast
+                _ => DEFAULT_SEEK_HEALTH,
+            });
+        HungerPolicy { seek_health }
+    }
+
+    // Works out how hungry snake `our_id` is in `state`.
+    pub fn assess(&self, state: &SimState, our_id: &str) -> Hunger {
+        let not_hungry = Hunger { urgency: 0.0, food_distance: None, health_at_food: None };
+        let you = match state.snakes.iter().find(|s| s.id == our_id) {
+            Some(s) => s,
+            None => return not_hungry,
+        };
+        if self.seek_health == 0 {
+            return not_hungry;
+        }
+
+        // The nearest food by shortest path, and the most health we can have left there once
+        // hazard damage is paid, taking the healthiest route rather than the shortest.
+        let distances = pathfinding::distance_map(state, our_id);
+        let food_distance = state.food.iter().filter_map(|food| distances.get(food)).min().copied();
+        let all_moves = [Move::Up, Move::Down, Move::Left, Move::Right];
+        let health_at_food = food_distance.and_then(|nearest| {
+            state.food.iter()
+                .filter(|food| distances.get(food) == Some(&nearest))
+                .filter_map(|food| pathfinding::healthiest_path(state, our_id, food, &all_moves))
+                .map(|p| p.health_left)
+                .max()
+        });
+
+        // Without reachable food, plan on what we have. Food we cannot live to reach leaves none.
+        let slack = match (food_distance, health_at_food) {
+            (Some(_), None) => 0,
+            _ => health_at_food.unwrap_or(you.health),
+        };
+        let mut urgency = (self.seek_health.saturating_sub(slack) as f32 / self.seek_health as f32).clamp(0.0, 1.0);
+
+        let longest_opponent = state.opponents(our_id).map(|s| s.length()).max();
+        if let Some(longest) = longest_opponent {
+            if longest >= you.length() {
+                // Equal length loses head-to-heads too, so count it as one short.
+                let deficit = (longest + 1 - you.length()) as f32;
+                urgency += (1.0 - urgency) * (deficit * LENGTH_DEFICIT_STEP).min(MAX_LENGTH_URGENCY);
+            } else if you.length() - longest >= COMFORTABLE_LEAD && slack > CRITICAL_SLACK {
+                urgency *= LEAD_RELIEF;
+            }
+        }
+
+        debug!(
+            "Hunger for {}: urgency {:.2} (health {}, food {:?} moves away, {:?} health left there)",
+            our_id, urgency, you.health, food_distance, health_at_food
+        );
+        Hunger { urgency, food_distance, health_at_food }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    // Our urgency with `health`, three moves from food at (3, 6), against `opponents`.
+    fn urgency(health: u32, body: &[(i32, i32)], opponents: Vec<SimSnake>) -> f32 {
+        let mut snakes = vec![SimSnake::new("me", body).with_health(health)];
+        snakes.extend(opponents);
+        let state = SimState::empty(7, 7).with_snakes(snakes).with_food(&[(3, 6)]);
+        let hunger = HungerPolicy::default().assess(&state, "me");
+        assert_eq!(hunger.food_distance, Some(3));
+        hunger.urgency
+    }
+
+    const SHORT: [(i32, i32); 3] = [(3, 3), (3, 2), (3, 1)];
+    const LONG: [(i32, i32); 6] = [(3, 3), (3, 2), (3, 1), (3, 0), (2, 0), (1, 0)];
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn urgency_rises_as_health_at_the_food_falls() {
+        assert_eq!(urgency(100, &SHORT, vec![]), 0.0);
+        // 25 health left at the food is halfway below the default 50.
+        assert!(close(urgency(28, &SHORT, vec![]), 0.5));
+        assert!(close(urgency(3, &SHORT, vec![]), 1.0));
+        // Rulesets where nobody starves never get hungry.
+        let starving = SimState::empty(7, 7).with_snakes(vec![SimSnake::new("me", &SHORT).with_health(5)]);
+        assert!(!HungerPolicy { seek_health: 0 }.assess(&starving, "me").should_seek_food());
+    }
+
+    #[test]
+    fn food_distance_is_the_shortest_path_even_through_hazard() {
+        // Straight up through the hazard is three moves; round it is five and costs far less.
+        let state = SimState::empty(7, 7)
+            .with_ruleset("royale")
+            .with_snakes(vec![SimSnake::new("me", &SHORT)])
+            .with_food(&[(3, 6)])
+            .with_hazards(&[(3, 4), (3, 5)]);
+        let hunger = HungerPolicy::default().assess(&state, "me");
+        assert_eq!(hunger.food_distance, Some(3));
+        assert_eq!(hunger.health_at_food, Some(90 - 5));
+    }
+
+    #[test]
+    fn length_against_opponents_shifts_urgency() {
+        // Two segments short (one for the tie) closes a fifth of the remaining gap.
+        let longer = SimSnake::new("them", &[(6, 6), (6, 5), (6, 4), (6, 3)]);
+        assert!(close(urgency(28, &SHORT, vec![longer]), 0.6));
+
+        // A comfortable lead halves it, unless we are nearly starving.
+        let shorter = SimSnake::new("them", &[(6, 6), (6, 5), (6, 4)]);
+        assert!(close(urgency(28, &LONG, vec![shorter.clone()]), 0.25));
+        assert!(close(urgency(11, &LONG, vec![shorter]), 0.84));
+    }
+
+    #[test]
+    fn a_healthy_snake_in_an_even_duel_does_not_seek_food() {
+        let state = |health| {
+            SimState::empty(7, 7)
+                .with_snakes(vec![SimSnake::new("me", &SHORT).with_health(health), SimSnake::new("them", &[(6, 6), (6, 5), (6, 4)])])
+                .with_food(&[(3, 6)])
+        };
+        let hunger = HungerPolicy::default().assess(&state(100), "me");
+        assert!(close(hunger.urgency, 0.1));
+        assert!(!hunger.should_seek_food());
+        assert!(HungerPolicy::default().assess(&state(28), "me").should_seek_food());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::game_state::{GameState, Move};
-use crate::logic::search::minimax_search;
+use crate::sim::state::SimState;
 use log::{debug, info, warn};
 use std::env; // Added for environment variable access
 
@@ -9,6 +9,15 @@ pub mod food;
 pub mod head_to_head;
 pub mod evaluation;
 pub mod search;
+pub mod voronoi;
+pub mod board_analysis;
+pub mod weights;
+pub mod pathfinding;
+pub mod food_race;
+pub mod food_safety;
+pub mod hunger;
+pub mod tactics;
+pub mod survival;
 
 // --- Constants for Ruleset Names (match API spec) ---
 const RULESET_STANDARD: &str = "standard";
@@ -36,25 +45,49 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
         );
     } else {
         info!(
-            "Game {} Turn {}: Ruleset: '{}' ----- Deciding Move ----- ",
-            game_id, turn, effective_ruleset_name
+            "Game {} Turn {}: Ruleset: '{}', map: '{}' ----- Deciding Move ----- ",
+            game_id, turn, effective_ruleset_name, game_state.game.map
         );
     }
 
-    // --- Ruleset-Specific Adjustments (Early) ---
-    let is_wrapped_mode = effective_ruleset_name == RULESET_WRAPPED;
-    // TODO: Pass `is_wrapped_mode` to safe_move::get_safe_moves, sim::state::apply_moves, 
-    //       flood_fill, and evaluation functions for boundary condition changes.
+    // Every layer below works on the simulator's view of the request, converted once.
+    let sim_state = SimState::from_api_state(game_state);
+    let our_id = game_state.you.id.as_str();
 
-    // L0-L3 Safe Moves
-    let safe_moves = safe_move::get_safe_moves(game_state); // This uses game_state, so it naturally gets engine ruleset
-                                                          // If wrapped mode affects safe_moves, it needs the effective_ruleset_name or is_wrapped_mode.
+    // L0-L3 Safe Moves: classify every direction, drop moves that die within a few turns, then keep
+    // the best tier that is left. Collision rules (wrapped edges, constrictor tails, hazards) come
+    // from the engine's ruleset.
+    let classified_moves = safe_move::generate_moves(&sim_state, our_id);
+    for safety in &classified_moves {
+        debug!("[{:?}] {:?}: {:?} {:?}", start_time.elapsed(), safety.direction, safety.tier, safety.reasons);
+    }
+    let survival_depth = survival::depth_for_budget(game_state.game.timeout);
+    let surviving_moves = survival::drop_dying_moves(&sim_state, our_id, &classified_moves, survival_depth);
+    debug!(
+        "[{:?}] Moves surviving {} turns: {:?}",
+        start_time.elapsed(), survival_depth, surviving_moves.iter().map(|m| m.direction).collect::<Vec<_>>()
+    );
+    let safe_moves = safe_move::best_moves(&surviving_moves);
     debug!("[{:?}] Safe moves (L0-L3): {:?}", start_time.elapsed(), safe_moves);
+    let safe_moves = safe_move::avoid_sealable_pockets(&sim_state, our_id, &safe_moves);
+    debug!("[{:?}] Safe moves after chokepoint check: {:?}", start_time.elapsed(), safe_moves);
 
     if safe_moves.is_empty() {
         warn!("[{:?}] No safe moves found!", start_time.elapsed());
         return Err("No safe moves available".to_string());
     }
+
+    // L5 Tactics: take a near-certain kill outright, among the moves that passed every check above
+    if let Some(tactic) = tactics::find_tactics(&sim_state, our_id, &safe_moves).first() {
+        if tactic.payoff >= tactics::DECISIVE_PAYOFF {
+            info!(
+                "[{:?}] Chose move {} via L5 Tactics ({:?} on {}, payoff {}).",
+                start_time.elapsed(), tactic.our_move.as_str(), tactic.tactic, tactic.target, tactic.payoff
+            );
+            return Ok(tactic.our_move);
+        }
+    }
+
     if safe_moves.len() == 1 {
         info!(
             "[{:?}] Only one safe move: {:?}. Choosing early.",
@@ -65,10 +98,9 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
 
     // --- Heuristic Layers & Ruleset Adjustments ---
     // TODO: Move config (depth, flags, weights) to a struct/env vars
-    let mut enable_search = true;
-    let mut search_depth = 4;
-    let mut food_seek_health_threshold = food::DEFAULT_FOOD_THRESHOLD;
-    let mut enable_flood_fill = true;
+    let enable_search = true;
+    let search_depth = 4;
+    let enable_flood_fill = true;
 
     match effective_ruleset_name {
         RULESET_STANDARD | RULESET_SOLO => {
@@ -77,29 +109,39 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
         }
         RULESET_CONSTRICTOR => {
             info!("[{:?}] Applying Constrictor ruleset logic.", start_time.elapsed());
-            food_seek_health_threshold = 15;
-            // search_depth = 5; // Consider deeper search for trapping
+            // Growth every turn and no starvation are simulated by SimState; the constrictor weight
+            // profile plays for space and cut-offs, and hunger is off.
         }
         RULESET_ROYALE => {
             info!("[{:?}] Applying Royale ruleset logic.", start_time.elapsed());
-            food_seek_health_threshold = 60;
-            // TODO: Modify SimState/evaluation/flood_fill to handle hazards.
-            // TODO: safe_moves needs to check for hazards in Royale.
+            // Hazard damage is part of the move generator's rules; see safe_move::generate_moves.
+            // The search projects upcoming hazard shrinks as odds (SimState::project_shrink), and
+            // the hazard evaluation feature keeps us inside the safe zone.
+        }
+        RULESET_SQUAD => {
+            info!("[{:?}] Applying Squad ruleset logic.", start_time.elapsed());
+            // Squadmates are allies: SimState::opponents leaves them out, and their bodies are
+            // passable when the squad settings allow body collisions.
         }
         RULESET_WRAPPED => {
-            info!("[{:?}] Applying Wrapped ruleset logic (boundary checks are TODO).", start_time.elapsed());
-            // Primary change is boundary logic, passed via is_wrapped_mode where needed.
+            info!("[{:?}] Applying Wrapped ruleset logic.", start_time.elapsed());
+            // Edges wrap through SimState::step, used by move generation, flood fill and pathfinding.
         }
         _ => {
             warn!("[{:?}] Unknown ruleset '{}', using default heuristics.", start_time.elapsed(), effective_ruleset_name);
         }
     }
 
+    // Hunger drives both the search evaluation and the L2 food layer.
+    let hunger = hunger::HungerPolicy::for_ruleset(effective_ruleset_name)
+        .assess(&sim_state, our_id);
+    debug!("[{:?}] Hunger: {:?}", start_time.elapsed(), hunger);
+
     // 1. L4: Minimax Search
     if enable_search {
-        // TODO: Pass effective_ruleset_name or derived config to search/evaluation 
-        //       if their internal logic needs to adapt (e.g., different eval weights).
-        let search_result = search::minimax_search(game_state, search_depth /*, &ruleset_config */);
+        let weight_profile = weights::profile_for_ruleset(effective_ruleset_name);
+        info!("[{:?}] Starting L4 Minimax Search (depth {}).", start_time.elapsed(), search_depth);
+        let search_result = search::minimax_search_sim(&sim_state, our_id, &safe_moves, search_depth, &weight_profile, hunger.urgency);
         if let Some(search_move) = search_result {
             info!(
                 "[{:?}] Chose move {} via L4 Minimax Search.",
@@ -114,13 +156,13 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
         }
     }
 
-    // 2. L2: Health & Food Management (using potentially adjusted threshold)
-    if game_state.you.health < food_seek_health_threshold {
+    // 2. L2: Health & Food Management
+    if hunger.should_seek_food() {
         debug!(
-            "[{:?}] Checking L2 Food Logic (Health: {}, Threshold: {}).",
-            start_time.elapsed(), game_state.you.health, food_seek_health_threshold
+            "[{:?}] Checking L2 Food Logic (Health: {}, Urgency: {:.2}).",
+            start_time.elapsed(), game_state.you.health, hunger.urgency
         );
-        if let Some(food_move) = food::find_move_to_closest_food(game_state, &safe_moves) {
+        if let Some(food_move) = food::find_move_to_closest_food(&sim_state, our_id, &safe_moves) {
             info!(
                 "[{:?}] Chose move {} via L2 Food Logic.",
                 start_time.elapsed(), food_move.as_str()
@@ -131,11 +173,21 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
         }
     }
 
-    // 3. L1: Flood Fill Space Heuristic
+    // 3. Hazard escape: leave the hazard we are in along the path that costs the least health
+    if let Some(escape) = pathfinding::hazard_escape_path(&sim_state, our_id, &safe_moves) {
+        if let Some(escape_move) = pathfinding::first_move(&sim_state, &game_state.you.head, &escape.path) {
+            info!(
+                "[{:?}] Chose move {} to escape hazard ({} moves, {} health left).",
+                start_time.elapsed(), escape_move.as_str(), escape.path.len(), escape.health_left
+            );
+            return Ok(escape_move);
+        }
+    }
+
+    // 4. L1: Flood Fill Space Heuristic
     if enable_flood_fill {
         debug!("[{:?}] Checking L1 Flood Fill Logic.", start_time.elapsed());
-        // TODO: flood_fill::evaluate_moves_by_space needs to handle wrapped and hazards based on effective_ruleset_name
-        let scored_moves = flood_fill::evaluate_moves_by_space(game_state, &safe_moves);
+        let scored_moves = flood_fill::evaluate_moves_by_space(&sim_state, our_id, &safe_moves);
         debug!("[{:?}] L1 Scored moves: {:?}", start_time.elapsed(), scored_moves);
 
         if let Some((best_move, _score)) = scored_moves.first() {
@@ -149,7 +201,7 @@ pub fn decide_move(game_state: &GameState) -> Result<Move, String> {
         }
     }
 
-    // 4. Fallback: First available safe move (L0)
+    // 5. Fallback: First available safe move (L0)
     let fallback_move = safe_moves.first().cloned().ok_or_else(|| {
         "Critical error: No safe moves available and no fallback determined.".to_string()
     })?;
@@ -0,0 +1,312 @@
+use crate::game_state::{Coord, Move};
+use crate::sim::state::SimState;
+use super::flood_fill::Vacancy;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+// A* open set entry: (estimated total, steps so far, square). Min-ordered through Reverse, so ties
+// on the estimate go to the deeper node.
+type OpenEntry = (u32, Reverse<u32>, (i32, i32));
+
+// A* search for the shortest path snake `snake_id` can take from its head to `goal`.
+// Body segments block a square only until they have moved away (see flood_fill::Vacancy), and the
+// first step is restricted to `first_moves` so callers can rule out moves they consider unsafe.
+// Returns the squares visited after the head, ending with `goal`, or None if it is unreachable.
+pub fn shortest_path(sim_state: &SimState, snake_id: &str, goal: &Coord, first_moves: &[Move]) -> Option<Vec<Coord>> {
+    let head = *sim_state.snakes.iter().find(|s| s.id == snake_id)?.head()?;
+    if !sim_state.in_bounds(goal) || head == *goal {
+        return None;
+    }
+    let vacancy = Vacancy::new(sim_state, snake_id);
+
+    let mut open: BinaryHeap<Reverse<OpenEntry>> = BinaryHeap::new();
+    let mut came_from: HashMap<Coord, Coord> = HashMap::new();
+    let mut best_steps: HashMap<Coord, u32> = HashMap::new();
+    let mut eaten: HashMap<Coord, u32> = HashMap::new(); // Food eaten on the best path to a square
+
+    best_steps.insert(head, 0);
+    eaten.insert(head, 0);
+    open.push(Reverse((head.manhattan_distance(goal), Reverse(0), (head.x, head.y))));
+
+    while let Some(Reverse((_, Reverse(steps), (x, y)))) = open.pop() {
+        let current = Coord { x, y };
+        if current == *goal {
+            let mut path = vec![current];
+            let mut at = current;
+            while let Some(prev) = came_from.get(&at) {
+                if *prev == head {
+                    break;
+                }
+                path.push(*prev);
+                at = *prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if best_steps.get(&current).is_some_and(|&best| steps > best) {
+            continue; // Stale entry
+        }
+
+        let next_steps = steps + 1;
+        let eaten_here = eaten[&current];
+        for m in [Move::Up, Move::Down, Move::Left, Move::Right] {
+            if current == head && !first_moves.contains(&m) {
+                continue;
+            }
+            let neighbor = match sim_state.step(&current, m) {
+                Some(n) => n,
+                None => continue,
+            };
+            if !vacancy.is_free(&neighbor, next_steps, eaten_here) {
+                continue;
+            }
+            if best_steps.get(&neighbor).is_some_and(|&best| best <= next_steps) {
+                continue;
+            }
+            best_steps.insert(neighbor, next_steps);
+            came_from.insert(neighbor, current);
+            eaten.insert(neighbor, eaten_here + sim_state.food.contains(&neighbor) as u32);
+            let estimate = next_steps + neighbor.manhattan_distance(goal);
+            open.push(Reverse((estimate, Reverse(next_steps), (neighbor.x, neighbor.y))));
+        }
+    }
+    None
+}
+
+// Dijkstra open set entry: (health left, steps so far, square). Max-ordered, so the healthiest
+// square is settled first and ties go to the one reached sooner.
+type HealthEntry = (u32, Reverse<u32>, (i32, i32));
+
+// A path together with the health the snake has left on arriving at its end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthPath {
+    pub path: Vec<Coord>, // Squares visited after the head, as returned by `shortest_path`
+    pub health_left: u32, // Health on arrival, before any food on the last square is eaten
+}
+
+// Path to `goal` that leaves snake `snake_id` with the most health, for boards with hazards.
+// Every move costs 1 health and ending a move in a hazard costs `hazard_damage` more. Paths on
+// which the snake would starve are never returned; food along the way is not counted on to
+// restore health, so this errs on the side of rejecting a path.
+pub fn healthiest_path(sim_state: &SimState, snake_id: &str, goal: &Coord, first_moves: &[Move]) -> Option<HealthPath> {
+    if !sim_state.in_bounds(goal) {
+        return None;
+    }
+    healthiest_path_to(sim_state, snake_id, first_moves, |c| c == goal)
+}
+
+// Healthiest path out of the hazard our head is in, to the nearest square (by health) outside
+// every hazard. None when the head is not in a hazard or no way out can be survived.
+pub fn hazard_escape_path(sim_state: &SimState, snake_id: &str, first_moves: &[Move]) -> Option<HealthPath> {
+    let head = sim_state.snakes.iter().find(|s| s.id == snake_id)?.head()?;
+    if !sim_state.hazards.contains(head) {
+        return None;
+    }
+    healthiest_path_to(sim_state, snake_id, first_moves, |c| !sim_state.hazards.contains(c))
+}
+
+// Dijkstra on remaining health. Every move costs at least 1 health, so the first time a square is
+// settled it has been reached with the most health possible.
+fn healthiest_path_to(
+    sim_state: &SimState,
+    snake_id: &str,
+    first_moves: &[Move],
+    is_goal: impl Fn(&Coord) -> bool,
+) -> Option<HealthPath> {
+    let snake = sim_state.snakes.iter().find(|s| s.id == snake_id)?;
+    let head = *snake.head()?;
+    let vacancy = Vacancy::new(sim_state, snake_id);
+
+    let mut open: BinaryHeap<HealthEntry> = BinaryHeap::new();
+    let mut came_from: HashMap<Coord, Coord> = HashMap::new();
+    let mut best_health: HashMap<Coord, u32> = HashMap::new();
+    let mut arrival: HashMap<Coord, (u32, u32)> = HashMap::new(); // (steps, food eaten) on the best path
+
+    best_health.insert(head, snake.health);
+    arrival.insert(head, (0, 0));
+    open.push((snake.health, Reverse(0), (head.x, head.y)));
+
+    while let Some((health, Reverse(steps), (x, y))) = open.pop() {
+        let current = Coord { x, y };
+        if best_health[&current] != health || arrival[&current].0 != steps {
+            continue; // Stale entry
+        }
+        if current != head && is_goal(&current) {
+            let mut path = vec![current];
+            let mut at = current;
+            while let Some(prev) = came_from.get(&at) {
+                if *prev == head {
+                    break;
+                }
+                path.push(*prev);
+                at = *prev;
+            }
+            path.reverse();
+            return Some(HealthPath { path, health_left: health });
+        }
+
+        let (_, eaten) = arrival[&current];
+        let next_steps = steps + 1;
+        for m in [Move::Up, Move::Down, Move::Left, Move::Right] {
+            if current == head && !first_moves.contains(&m) {
+                continue;
+            }
+            let neighbor = match sim_state.step(&current, m) {
+                Some(n) => n,
+                None => continue,
+            };
+            if !vacancy.is_free(&neighbor, next_steps, eaten) {
+                continue;
+            }
+            let damage = 1 + if sim_state.hazards.contains(&neighbor) { sim_state.rules.hazard_damage } else { 0 };
+            let next_health = health.saturating_sub(damage);
+            if next_health == 0 && !sim_state.food.contains(&neighbor) {
+                continue; // Starves on the way
+            }
+            let better = match (best_health.get(&neighbor), arrival.get(&neighbor)) {
+                (Some(&h), Some(&(s, _))) => next_health > h || (next_health == h && next_steps < s),
+                _ => true,
+            };
+            if !better {
+                continue;
+            }
+            best_health.insert(neighbor, next_health);
+            arrival.insert(neighbor, (next_steps, eaten + sim_state.food.contains(&neighbor) as u32));
+            came_from.insert(neighbor, current);
+            open.push((next_health, Reverse(next_steps), (neighbor.x, neighbor.y)));
+        }
+    }
+    None
+}
+
+// First move along a path returned by `shortest_path`, starting from `head`. The first step may
+// cross an edge in wrapped games.
+pub fn first_move(sim_state: &SimState, head: &Coord, path: &[Coord]) -> Option<Move> {
+    sim_state.direction_to(head, path.first()?)
+}
+
+// Shortest path length from snake `snake_id`'s head to every square it can reach, found by BFS
+// with the same vacate-time rules as `shortest_path`.
+pub fn distance_map(sim_state: &SimState, snake_id: &str) -> HashMap<Coord, u32> {
+    let mut distances: HashMap<Coord, u32> = HashMap::new();
+    let head = match sim_state.snakes.iter().find(|s| s.id == snake_id).and_then(|s| s.head()) {
+        Some(h) => *h,
+        None => return distances,
+    };
+    let vacancy = Vacancy::new(sim_state, snake_id);
+
+    let mut queue: VecDeque<(Coord, u32)> = VecDeque::new(); // (square, food eaten on the way)
+    distances.insert(head, 0);
+    queue.push_back((head, 0));
+    while let Some((current, eaten)) = queue.pop_front() {
+        let next_steps = distances[&current] + 1;
+        for neighbor in sim_state.neighbours(&current) {
+            if distances.contains_key(&neighbor)
+                || !vacancy.is_free(&neighbor, next_steps, eaten)
+            {
+                continue;
+            }
+            distances.insert(neighbor, next_steps);
+            queue.push_back((neighbor, eaten + sim_state.food.contains(&neighbor) as u32));
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    fn coords(cs: &[(i32, i32)]) -> Vec<Coord> {
+        cs.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
+
+    // A 5x5 board under `ruleset` with its default settings.
+    fn board(ruleset: &str, snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(5, 5).with_ruleset(ruleset).with_snakes(snakes)
+    }
+
+    const ALL_MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+    // We start at (0, 2); "wall" runs up x = 2 from (2, 1), its stacked tail there leaving after
+    // two turns and (2, 2) after three.
+    fn walled(ruleset: &str) -> SimState {
+        board(ruleset, vec![
+            SimSnake::new("me", &[(0, 2), (0, 3), (0, 4)]),
+            SimSnake::new("wall", &[(2, 4), (2, 3), (2, 2), (2, 1), (2, 1)]),
+        ])
+    }
+
+    #[test]
+    fn shortest_path_waits_for_bodies_to_move() {
+        let goal = Coord { x: 4, y: 2 };
+        // Through (2, 1) on turn 3, just after it is vacated.
+        let path = shortest_path(&walled("standard"), "me", &goal, &ALL_MOVES).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.contains(&Coord { x: 2, y: 1 }));
+        assert_eq!(distance_map(&walled("standard"), "me").get(&goal), Some(&6));
+
+        // Tails never move in constrictor, so the way is round the bottom of the wall.
+        let path = shortest_path(&walled("constrictor"), "me", &goal, &ALL_MOVES).unwrap();
+        assert_eq!(path.len(), 8);
+        assert!(path.contains(&Coord { x: 2, y: 0 }));
+
+        // Squares sealed off behind the wall are unreachable.
+        let sealed = board("constrictor", vec![
+            SimSnake::new("me", &[(0, 2), (0, 3), (0, 4)]),
+            SimSnake::new("wall", &[(4, 2), (3, 2), (3, 3), (3, 4)]),
+        ]);
+        assert_eq!(shortest_path(&sealed, "me", &Coord { x: 4, y: 4 }, &ALL_MOVES), None);
+        assert!(!distance_map(&sealed, "me").contains_key(&Coord { x: 4, y: 4 }));
+    }
+
+    #[test]
+    fn shortest_path_respects_allowed_first_moves() {
+        let state = walled("standard");
+        let goal = Coord { x: 4, y: 2 };
+        let path = shortest_path(&state, "me", &goal, &[Move::Down]).unwrap();
+        assert_eq!(path[0], Coord { x: 0, y: 1 });
+        assert_eq!(path.len(), 6);
+
+        assert_eq!(shortest_path(&state, "me", &goal, &[]), None);
+        assert_eq!(shortest_path(&state, "me", &Coord { x: 5, y: 2 }, &ALL_MOVES), None);
+        assert_eq!(shortest_path(&state, "me", &Coord { x: 0, y: 2 }, &ALL_MOVES), None);
+    }
+
+    #[test]
+    fn healthiest_path_goes_round_hazards_it_can_afford_to_avoid() {
+        // Straight across costs 4 + 3 * 14 health; round the top costs 6.
+        let hazards = [(1, 2), (2, 2), (3, 2)];
+        let goal = Coord { x: 4, y: 2 };
+        let state = |health| {
+            board("royale", vec![SimSnake::new("me", &[(0, 2), (0, 1), (0, 0)]).with_health(health)]).with_hazards(&hazards)
+        };
+
+        let healthiest = healthiest_path(&state(90), "me", &goal, &ALL_MOVES).unwrap();
+        assert_eq!(healthiest.path.len(), 6);
+        assert_eq!(healthiest.health_left, 84);
+        assert!(healthiest.path.iter().all(|c| !hazards.contains(&(c.x, c.y))));
+        assert_eq!(shortest_path(&state(90), "me", &goal, &ALL_MOVES).map(|p| p.len()), Some(4));
+
+        // Arriving with no health left is starving, not arriving.
+        assert_eq!(healthiest_path(&state(7), "me", &goal, &ALL_MOVES).map(|p| p.health_left), Some(1));
+        assert_eq!(healthiest_path(&state(6), "me", &goal, &ALL_MOVES), None);
+    }
+
+    #[test]
+    fn hazard_escape_takes_the_cheapest_way_out() {
+        // Everything but the right-hand column is hazard; the way out is two hazard squares away.
+        let hazards: Vec<(i32, i32)> = (0..4).flat_map(|x| (0..5).map(move |y| (x, y))).collect();
+        let state = board("royale", vec![SimSnake::new("me", &[(1, 2), (1, 1), (1, 0)])]).with_hazards(&hazards);
+        let escape = hazard_escape_path(&state, "me", &ALL_MOVES).unwrap();
+        assert_eq!(escape.path, coords(&[(2, 2), (3, 2), (4, 2)]));
+        assert_eq!(escape.health_left, 90 - 2 * 15 - 1);
+        assert_eq!(first_move(&state, &Coord { x: 1, y: 2 }, &escape.path), Some(Move::Right));
+
+        // Only a head in a hazard needs an escape.
+        let outside = board("royale", vec![SimSnake::new("me", &[(4, 2), (4, 1), (4, 0)])]).with_hazards(&hazards);
+        assert_eq!(hazard_escape_path(&outside, "me", &ALL_MOVES), None);
+    }
+}
@@ -1,145 +1,418 @@
-use crate::game_state::{Coord, GameState, Move};
+use crate::game_state::{Coord, Move};
 use crate::sim::state::{SimSnake, SimState};
 use super::head_to_head; // Import the head_to_head logic
-use std::collections::HashSet; // Added HashSet import
+use super::board_analysis;
+use super::flood_fill;
 
-pub fn get_safe_moves(state: &GameState) -> Vec<Move> {
-    let my_head = &state.you.head;
-    let board = &state.board;
-    let self_id = &state.you.id;
+// How dangerous a move is, from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SafetyTier {
+    Deadly,     // Certain death: a wall, a body, or starving
+    LikelyLoss, // A likely losing head-to-head, or a pocket smaller than we are
+    Risky,      // A possible losing head-to-head, or a hazard with health running low
+    Safe,
+}
 
-    let possible_moves = [Move::Up, Move::Down, Move::Left, Move::Right];
-    let mut safe_moves = Vec::new();
+// Why a move was put in a tier below Safe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SafetyReason {
+    Wall,
+    OwnBody,
+    SnakeBody(String),                                // Snake ID
+    HeadToHead(head_to_head::HeadToHeadRisk),
+    SmallPocket { space: usize, length: usize },
+    Hazard { health_after: u32 },
+    Starving, // Out of health next turn without food
+}
 
-    for &direction in &possible_moves {
-        let target = my_head.apply_move(direction);
+// Classification of one of the four directions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveSafety {
+    pub direction: Move,
+    pub tier: SafetyTier,
+    pub reasons: Vec<SafetyReason>,
+    pub head_to_head: head_to_head::RiskTier, // Breaks ties between risky moves
+}
 
-        // 1. Wall collision check
-        if !board.in_bounds(&target) {
-            continue;
-        }
+// Health at or below which ending a move in a hazard is Risky rather than Safe, in multiples of the
+// hazard damage.
+const LOW_HEALTH_HAZARD_TURNS: u32 = 2;
 
-        // 2. Self collision check
-        if board.is_occupied_by_snake(&target, self_id) {
-             let tail_tip = state.you.body.last().unwrap_or(my_head);
-             if target == *tail_tip && state.you.length > 1 && target != state.you.head {
-                // Allow moving onto tail tip for now (can be refined)
-             } else {
-                continue; // Avoid self collision
-             }
-        }
+// Why moving `snake` onto `target` (None when off the board) is certain death, if it is. These are
+// the collision rules every move generator shares: walls (unless wrapped), body segments still
+// there next turn, and starving.
+fn deadly_reason(state: &SimState, snake: &SimSnake, target: Option<Coord>) -> Option<SafetyReason> {
+    let target = match target {
+        Some(t) => t,
+        None => return Some(SafetyReason::Wall),
+    };
 
-        // 3. Other snake body collision check (excluding tails and heads for now)
-        if board.is_occupied_by_others(&target, self_id, true) {
-            // Need to refine this: board.is_occupied_by_others excludes tails.
-            // We need to specifically check if the target is another snake's head.
-            let mut occupied_by_other_body_not_head = false;
-            for other_snake in state.board.snakes.iter().filter(|s| s.id != *self_id) {
-                // Check body segments *except* the head (index 0)
-                for segment in other_snake.body.iter().skip(1) {
-                    if *segment == target {
-                        occupied_by_other_body_not_head = true;
-                        break;
-                    }
-                }
-                if occupied_by_other_body_not_head { break; }
-            }
-            if occupied_by_other_body_not_head {
-                 continue; // Avoid non-head body parts
-            }
+    // Every segment stays put except a tail tip that moves on, when the rules let tails move and
+    // the snake did not just eat (a stacked tail stays for another turn). Squadmates' bodies can
+    // be passed through when the squad rules allow it.
+    let tails_vacate = state.rules.tails_vacate();
+    for other in state.snakes.iter().filter(|other| !state.can_pass_through(snake, other)) {
+        let kept = if tails_vacate && other.body.len() > 1 && !other.tail_is_stacked() { other.body.len() - 1 } else { other.body.len() };
+        if other.body.iter().take(kept).any(|segment| *segment == target) {
+            return Some(if other.id == snake.id { SafetyReason::OwnBody } else { SafetyReason::SnakeBody(other.id.clone()) });
         }
+    }
 
-        // 4. Head-to-head collision check (L3)
-        if head_to_head::is_dangerous_head_to_head(state, &target) {
-            continue; // Avoid dangerous head-to-head
+    // Every move costs 1 health and a hazard costs more, unless we eat there or cannot starve.
+    if state.rules.snakes_starve() && !state.food.contains(&target) {
+        let in_hazard = state.hazards.contains(&target);
+        let damage = 1 + if in_hazard { state.rules.hazard_damage } else { 0 };
+        let health_after = snake.health.saturating_sub(damage);
+        if health_after == 0 {
+            return Some(if in_hazard { SafetyReason::Hazard { health_after } } else { SafetyReason::Starving });
         }
-
-        // 5. Hazard check (Optional)
-        // if board.hazards.contains(&target) { continue; }
-
-        safe_moves.push(direction);
     }
+    None
+}
 
-    // Optional: If advantageous head-to-head moves exist, prioritize them?
-    // For now, just return all moves deemed safe by the above checks.
-
-    safe_moves
+// Moves that do not kill snake `snake_id` outright, ignoring what other snakes do this turn.
+// Cheap enough to run for every opponent, e.g. to predict where they may go.
+pub fn legal_moves(state: &SimState, snake_id: &str) -> Vec<Move> {
+    let snake = match state.snakes.iter().find(|s| s.id == snake_id) {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    let head = match snake.head() {
+        Some(h) => *h,
+        None => return Vec::new(),
+    };
+    [Move::Up, Move::Down, Move::Left, Move::Right].into_iter()
+        .filter(|&m| deadly_reason(state, snake, state.step(&head, m)).is_none())
+        .collect()
 }
 
-// Calculates safe moves for a specific snake within a SimState.
-pub fn get_sim_safe_moves(state: &SimState, snake_id: &str) -> Vec<Move> {
+// The move generator: classifies all four directions for snake `snake_id`, in Up, Down, Left,
+// Right order, under the rules of `state` (wrapped edges, constrictor tails, hazard damage). The
+// API and simulator paths both go through here.
+pub fn generate_moves(state: &SimState, snake_id: &str) -> Vec<MoveSafety> {
     let snake = match state.snakes.iter().find(|s| s.id == snake_id) {
         Some(s) => s,
-        None => return vec![], // Snake not found or already dead
+        None => return Vec::new(),
     };
     let head = match snake.head() {
-        Some(h) => h,
-        None => return vec![], // Snake has no head (empty body)
+        Some(h) => *h,
+        None => return Vec::new(),
     };
+    let head_to_head_risks = head_to_head::head_to_head_risks(state, snake_id);
 
-    let possible_moves = [Move::Up, Move::Down, Move::Left, Move::Right];
-    let mut safe_moves = Vec::new();
+    [Move::Up, Move::Down, Move::Left, Move::Right].into_iter().map(|direction| {
+        // 1-3. Walls, bodies and starving
+        let target = match state.step(&head, direction) {
+            Some(t) if deadly_reason(state, snake, Some(t)).is_none() => t,
+            target => {
+                let reason = deadly_reason(state, snake, target).unwrap_or(SafetyReason::Wall);
+                return MoveSafety {
+                    direction,
+                    tier: SafetyTier::Deadly,
+                    reasons: vec![reason],
+                    head_to_head: head_to_head::RiskTier::Safe,
+                };
+            }
+        };
+        let mut reasons = Vec::new();
+        let mut tier = SafetyTier::Safe;
 
-    // Precompute occupied set for efficiency
-    let occupied_coords: HashSet<Coord> = state.snakes.iter()
-        .flat_map(|s| s.body.iter().cloned())
-        .collect();
-    
-    // Precompute occupied excluding tails (for other snake check)
-     let occupied_excluding_tails: HashSet<Coord> = state.snakes.iter().flat_map(|s| {
-        // Iterate over body segments, excluding the tail tip if len > 1
-        s.body.iter().take(if s.body.len() > 1 { s.body.len() - 1 } else { s.body.len() })
-    }).cloned().collect();
-
-    for &direction in &possible_moves {
-        let target = head.apply_move(direction);
-
-        // 1. Wall collision check
-        if !state.in_bounds(&target) {
-            continue;
+        // 4. Hazard with health running low
+        if state.hazards.contains(&target) && !state.food.contains(&target) {
+            let health_after = snake.health.saturating_sub(1 + state.rules.hazard_damage);
+            if health_after <= LOW_HEALTH_HAZARD_TURNS * state.rules.hazard_damage {
+                tier = SafetyTier::Risky;
+                reasons.push(SafetyReason::Hazard { health_after });
+            }
         }
 
-        // 2. Body collision check (any snake, including self, excluding tails)
-        // Check against occupied_excluding_tails set
-        if occupied_excluding_tails.contains(&target) {
-             // Is the target *this* snake's tail tip? (Allow moving onto own tail)
-             if let Some(tail_tip) = snake.body.back() {
-                 if target == *tail_tip && snake.length() > 1 {
-                     // It's our own tail tip, potentially safe.
-                     // Need to ensure it's not *also* another snake's non-tail body part.
-                      let occupied_by_other_non_tail = state.snakes.iter()
-                            .filter(|s| s.id != snake_id)
-                            .flat_map(|s| s.body.iter().take(if s.body.len() > 1 { s.body.len() - 1 } else { s.body.len() }))
-                            .any(|seg| *seg == target);
-                     if occupied_by_other_non_tail {
-                         continue; // Tail occupied by other snake body
-                     }
-                     // Otherwise, allow moving onto own tail
-                 } else {
-                     continue; // Occupied by non-tail body part
-                 }
-             } else {
-                 continue; // Occupied by non-tail body part (or snake has no tail?)
-             }
+        // 5. Head-to-head collision check (L3)
+        let risk = head_to_head_risks.get(&target).copied().unwrap_or(head_to_head::HeadToHeadRisk::NONE);
+        match risk.tier {
+            head_to_head::RiskTier::Safe => {}
+            head_to_head::RiskTier::Unlikely | head_to_head::RiskTier::Possible => {
+                tier = tier.min(SafetyTier::Risky);
+                reasons.push(SafetyReason::HeadToHead(risk));
+            }
+            head_to_head::RiskTier::Likely | head_to_head::RiskTier::Certain => {
+                tier = tier.min(SafetyTier::LikelyLoss);
+                reasons.push(SafetyReason::HeadToHead(risk));
+            }
+        }
+
+        // 6. Pocket check: less room than our own length
+        let space = flood_fill::flood_fill_sim(state, snake_id, &target, 1);
+        let length = snake.length();
+        if space < length {
+            tier = tier.min(SafetyTier::LikelyLoss);
+            reasons.push(SafetyReason::SmallPocket { space, length });
         }
-        
-        // 3. Head-to-head collision check (basic version for sim)
-        // TODO: Refactor head_to_head logic to potentially work with SimState directly
-        // For now, skip this check in the simulation's safe move calculation 
-        // The main evaluation will handle head-to-head implicitly via outcomes.
-        // let temp_gamestate = state.to_api_state(); // Requires conversion function
-        // if head_to_head::is_dangerous_head_to_head(&temp_gamestate, &target) {
-        //     continue;
-        // }
 
-        // 4. Hazard check (if hazards are added to SimState)
+        MoveSafety { direction, tier, reasons, head_to_head: risk.tier }
+    }).collect()
+}
+
+// The moves worth considering: everything in the best tier that is not deadly, preferring the
+// smallest head-to-head risk within that tier. Empty when every move is deadly.
+pub fn best_moves(classified: &[MoveSafety]) -> Vec<Move> {
+    let best_tier = match classified.iter().map(|m| m.tier).max() {
+        Some(t) if t > SafetyTier::Deadly => t,
+        _ => return Vec::new(),
+    };
+    let in_tier: Vec<&MoveSafety> = classified.iter().filter(|m| m.tier == best_tier).collect();
+    let lowest_risk = in_tier.iter().map(|m| m.head_to_head).min().unwrap_or(head_to_head::RiskTier::Safe);
+    in_tier.into_iter().filter(|m| m.head_to_head == lowest_risk).map(|m| m.direction).collect()
+}
+
+// Drops moves into pockets that are too small for us and that an opponent can seal before we get
+// back out. If every move leads into such a pocket the original list is returned unchanged.
+pub fn avoid_sealable_pockets(sim_state: &SimState, our_id: &str, safe_moves: &[Move]) -> Vec<Move> {
+    let head = match sim_state.snakes.iter().find(|s| s.id == our_id).and_then(|s| s.head()) {
+        Some(h) => *h,
+        None => return safe_moves.to_vec(),
+    };
+    let analysis = board_analysis::analyze_free_space(sim_state);
+    let open_moves: Vec<Move> = safe_moves.iter()
+        .filter(|&&m| {
+            let target = sim_state.step(&head, m).unwrap_or(head);
+            board_analysis::sealable_pocket(sim_state, &analysis, our_id, &target, 1).is_none()
+        })
+        .cloned()
+        .collect();
 
-        safe_moves.push(direction);
+    if open_moves.is_empty() {
+        safe_moves.to_vec()
+    } else {
+        open_moves
     }
-    safe_moves
 }
 
-// Basic fallback if no safe moves are found
-pub fn fallback_safe_move() -> Move {
-    Move::Down
-} 
\ No newline at end of file
+// Calculates safe moves for a specific snake within a SimState.
+pub fn get_sim_safe_moves(state: &SimState, snake_id: &str) -> Vec<Move> {
+    best_moves(&generate_moves(state, snake_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GameState;
+    use serde_json::json;
+
+    fn snake(id: &str, health: u32, body: &[(i32, i32)]) -> serde_json::Value {
+        let body: Vec<_> = body.iter().map(|&(x, y)| json!({ "x": x, "y": y })).collect();
+        json!({ "id": id, "name": id, "health": health, "body": body, "head": body[0], "length": body.len() })
+    }
+
+    // Builds a /move payload in which "you" is the first snake.
+    fn game(ruleset: &str, size: i32, snakes: Vec<serde_json::Value>, food: &[(i32, i32)], hazards: &[(i32, i32)]) -> GameState {
+        let coords = |cs: &[(i32, i32)]| cs.iter().map(|&(x, y)| json!({ "x": x, "y": y })).collect::<Vec<_>>();
+        serde_json::from_value(json!({
+            "game": {
+                "id": "test",
+                "ruleset": { "name": ruleset, "version": "v1", "settings": { "hazardDamagePerTurn": 14 } },
+                "timeout": 500
+            },
+            "turn": 5,
+            "board": { "height": size, "width": size, "food": coords(food), "hazards": coords(hazards), "snakes": snakes.clone() },
+            "you": snakes[0]
+        })).expect("valid payload")
+    }
+
+    // Classifies all four directions for "you", in Up, Down, Left, Right order.
+    fn classify_moves(state: &GameState) -> Vec<MoveSafety> {
+        generate_moves(&SimState::from_api_state(state), &state.you.id)
+    }
+
+    fn safe_moves(state: &GameState) -> Vec<Move> {
+        best_moves(&classify_moves(state))
+    }
+
+    fn tier(state: &GameState, direction: Move) -> SafetyTier {
+        classify_moves(state).into_iter().find(|m| m.direction == direction).unwrap().tier
+    }
+
+    #[test]
+    fn walls_and_own_neck_are_deadly() {
+        let state = game("standard", 11, vec![snake("me", 90, &[(0, 0), (1, 0), (2, 0)])], &[], &[]);
+        assert_eq!(safe_moves(&state), vec![Move::Up]);
+    }
+
+    #[test]
+    fn own_tail_tip_is_free() {
+        let state = game("standard", 11, vec![snake("me", 90, &[(1, 1), (1, 2), (2, 2), (2, 1)])], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Safe);
+        assert_eq!(tier(&state, Move::Up), SafetyTier::Deadly);
+    }
+
+    #[test]
+    fn other_snakes_block_except_their_tail_tip() {
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(5, 5), (5, 4), (5, 3)]),
+            snake("them", 90, &[(6, 7), (6, 6), (6, 5), (7, 5)]),
+        ], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Deadly);
+
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(5, 5), (5, 4), (5, 3)]),
+            snake("them", 90, &[(8, 6), (7, 6), (6, 6), (6, 5)]),
+        ], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Safe);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left, Move::Right]);
+    }
+
+    #[test]
+    fn an_opponents_current_head_square_is_deadly() {
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(5, 5), (5, 4), (5, 3)]),
+            snake("them", 90, &[(6, 5), (7, 5), (8, 5), (9, 5)]),
+        ], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Deadly);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left]);
+    }
+
+    #[test]
+    fn head_to_head_with_equal_snake_is_avoided() {
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(5, 5), (5, 4), (5, 3)]),
+            snake("them", 90, &[(7, 5), (8, 5), (9, 5)]),
+        ], &[], &[]);
+        assert!(tier(&state, Move::Right) < SafetyTier::Safe);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left]);
+    }
+
+    #[test]
+    fn head_to_head_with_shorter_snake_is_safe() {
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(5, 5), (5, 4), (5, 3), (5, 2)]),
+            snake("them", 90, &[(7, 5), (8, 5), (9, 5)]),
+        ], &[], &[]);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left, Move::Right]);
+    }
+
+    #[test]
+    fn wrapped_moves_cross_the_edges() {
+        let state = game("wrapped", 11, vec![snake("me", 90, &[(0, 10), (1, 10), (2, 10)])], &[], &[]);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Down, Move::Left]);
+
+        let standard = game("standard", 11, vec![snake("me", 90, &[(0, 10), (1, 10), (2, 10)])], &[], &[]);
+        assert_eq!(safe_moves(&standard), vec![Move::Down]);
+    }
+
+    #[test]
+    fn wrapped_paths_start_across_the_edge() {
+        use crate::logic::{food, pathfinding};
+
+        // The food is one step away, across the left edge.
+        let body = [(0, 5), (1, 5), (2, 5)];
+        let state = game("wrapped", 11, vec![snake("me", 90, &body)], &[(10, 5)], &[]);
+        let moves = safe_moves(&state);
+        assert_eq!(food::find_move_to_closest_food(&SimState::from_api_state(&state), "me", &moves), Some(Move::Left));
+
+        // The only square out of the hazard is across the edge too.
+        let hazards: Vec<(i32, i32)> = (0..11).flat_map(|x| (0..11).map(move |y| (x, y))).filter(|&c| c != (10, 5)).collect();
+        let state = game("wrapped", 11, vec![snake("me", 90, &body)], &[], &hazards);
+        let moves = safe_moves(&state);
+        let sim_state = SimState::from_api_state(&state);
+        let escape = pathfinding::hazard_escape_path(&sim_state, "me", &moves).expect("a way out");
+        assert_eq!(pathfinding::first_move(&sim_state, &state.you.head, &escape.path), Some(Move::Left));
+    }
+
+    #[test]
+    fn constrictor_tails_never_move() {
+        let body = [(1, 1), (1, 2), (2, 2), (2, 1)];
+        let state = game("constrictor", 11, vec![snake("me", 100, &body)], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Deadly);
+
+        let state = game("standard", 11, vec![snake("me", 100, &body)], &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Safe);
+    }
+
+    #[test]
+    fn hazards_starve_or_weaken() {
+        let body = [(5, 5), (5, 4), (5, 3)];
+        let hazards = [(5, 6)];
+
+        let state = game("royale", 11, vec![snake("me", 15, &body)], &[], &hazards);
+        assert_eq!(tier(&state, Move::Up), SafetyTier::Deadly);
+
+        let state = game("royale", 11, vec![snake("me", 30, &body)], &[], &hazards);
+        assert_eq!(tier(&state, Move::Up), SafetyTier::Risky);
+        assert_eq!(safe_moves(&state), vec![Move::Left, Move::Right]);
+
+        let state = game("royale", 11, vec![snake("me", 90, &body)], &[], &hazards);
+        assert_eq!(tier(&state, Move::Up), SafetyTier::Safe);
+
+        // Food in the hazard saves us.
+        let state = game("royale", 11, vec![snake("me", 15, &body)], &[(5, 6)], &hazards);
+        assert_eq!(tier(&state, Move::Up), SafetyTier::Safe);
+    }
+
+    #[test]
+    fn small_pockets_are_a_likely_loss() {
+        // Moving left enters a one-square dead end between our own body and the opponent's.
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(1, 2), (1, 1), (0, 1), (0, 0), (1, 0), (2, 0)]),
+            snake("them", 90, &[(6, 4), (6, 3), (5, 3), (4, 3), (3, 3), (2, 3), (1, 3), (0, 3), (0, 4), (0, 5)]),
+        ], &[], &[]);
+        let left = classify_moves(&state).into_iter().find(|m| m.direction == Move::Left).unwrap();
+        assert!(left.reasons.iter().any(|r| matches!(r, SafetyReason::SmallPocket { .. })), "{:?}", left);
+        assert_eq!(safe_moves(&state), vec![Move::Right]);
+    }
+
+    // Synthetic payload, written by hand in the engine's format, for the turn after we ate the
+    // food at (2, 4): our tail is stacked on (2, 3) and stays there for another turn.
+    const AFTER_EATING: &str = include_str!("../../testdata/after_eating.json");
+
+    fn payload(json: &str) -> GameState {
+        serde_json::from_str(json).expect("valid payload")
+    }
+
+    #[test]
+    fn own_stacked_tail_blocks_after_eating() {
+        let state = payload(AFTER_EATING);
+        let down = classify_moves(&state).into_iter().find(|m| m.direction == Move::Down).unwrap();
+        assert_eq!(down.tier, SafetyTier::Deadly);
+        assert_eq!(down.reasons, vec![SafetyReason::OwnBody]);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left]);
+        assert!(flood_fill::static_obstacles(&SimState::from_api_state(&state)).contains(&Coord { x: 2, y: 3 }));
+    }
+
+    #[test]
+    fn opponent_stacked_tail_blocks_after_eating() {
+        let state = game("standard", 11, vec![
+            snake("me", 90, &[(6, 6), (5, 6), (4, 6)]),
+            snake("them", 100, &[(8, 8), (8, 7), (7, 7), (6, 7), (6, 7)]),
+        ], &[], &[]);
+        let up = classify_moves(&state).into_iter().find(|m| m.direction == Move::Up).unwrap();
+        assert_eq!(up.reasons, vec![SafetyReason::SnakeBody("them".to_string())]);
+        assert_eq!(safe_moves(&state), vec![Move::Down, Move::Right]);
+    }
+
+    #[test]
+    fn fully_stacked_snake_at_game_start_can_move_anywhere() {
+        let state = game("standard", 11, vec![snake("me", 100, &[(1, 1), (1, 1), (1, 1)])], &[], &[]);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Down, Move::Left, Move::Right]);
+    }
+
+    fn squad_snake(id: &str, squad: &str, health: u32, body: &[(i32, i32)]) -> serde_json::Value {
+        let mut snake = snake(id, health, body);
+        snake["squad"] = json!(squad);
+        snake
+    }
+
+    #[test]
+    fn squadmate_bodies_are_passable_in_squad_games() {
+        let snakes = vec![
+            squad_snake("me", "red", 90, &[(5, 5), (5, 4), (5, 3)]),
+            squad_snake("mate", "red", 90, &[(8, 6), (7, 6), (6, 6), (6, 5), (6, 4)]),
+            squad_snake("them", "blue", 90, &[(2, 2), (2, 1), (2, 0)]),
+        ];
+        let state = game("squad", 11, snakes.clone(), &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Safe);
+        assert_eq!(SimState::from_api_state(&state).opponents("me").map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["them"]);
+        assert_eq!(safe_moves(&state), vec![Move::Up, Move::Left, Move::Right]);
+
+        let state = game("standard", 11, snakes, &[], &[]);
+        assert_eq!(tier(&state, Move::Right), SafetyTier::Deadly);
+    }
+}
@@ -1,27 +1,33 @@
-use crate::game_state::{GameState, Move};
-use crate::sim::state::{SimState, SimSnake};
+use crate::game_state::Move;
+use crate::sim::state::SimState;
 use crate::logic::safe_move::get_sim_safe_moves;
 use crate::logic::flood_fill::flood_fill_sim;
 use super::evaluation;
+use super::weights::{EvalWeights, WeightProfile};
 use log::{debug, warn, info};
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 const MAX_SEARCH_TIME_MS: u128 = 400; // Max time before fallback (adjust as needed)
 
+// Per-search values that stay fixed while recursing.
+struct SearchContext<'a> {
+    our_id: &'a str,
+    root_opponents: usize, // Opponents alive at the root, for outlived-opponent credit
+    start_time: Instant,
+    weights: &'a EvalWeights,
+}
+
 // --- Top-level Search Function ---
 
-// Finds the best move using minimax search within a time limit.
-pub fn minimax_search(state: &GameState, depth: u8) -> Option<Move> {
+// Finds the best move for snake `our_id` among `root_moves` using minimax search within a time
+// limit. Leaves are scored with the weights `profile` gives for the current position and hunger
+// urgency. `root_moves` are the first moves worth considering, already vetted by the caller.
+pub fn minimax_search_sim(sim_state_initial: &SimState, our_id: &str, root_moves: &[Move], depth: u8, profile: &WeightProfile, hunger: f32) -> Option<Move> {
     let overall_start_time = Instant::now();
-    info!(
-        "Game {} Turn {}: === Starting Minimax search (depth {}) ===",
-        state.game.id, state.turn, depth
-    );
-    let sim_state_initial = SimState::from_api_state(state);
-    let our_id = &sim_state_initial.snakes.iter().find(|s| s.id == state.you.id)?.id.clone(); // Find our ID in sim state
+    sim_state_initial.snakes.iter().find(|s| s.id == our_id)?; // We must be alive to search
 
-    let legal_moves = get_sim_safe_moves(&sim_state_initial, our_id);
+    let legal_moves = root_moves.to_vec();
     if legal_moves.is_empty() {
         warn!("Minimax Search: No legal moves found initially!");
         return None;
@@ -31,22 +37,33 @@ pub fn minimax_search(state: &GameState, depth: u8) -> Option<Move> {
         return Some(legal_moves[0]);
     }
 
+    let weights = profile.weights_for(sim_state_initial, our_id, hunger);
+    debug!(
+        "Minimax Search: phase {:.2}, hunger {:.2}, evaluation weights {:?}",
+        profile.phase(sim_state_initial, our_id), hunger, weights
+    );
+
+    let ctx = SearchContext {
+        our_id,
+        root_opponents: sim_state_initial.opponents(our_id).count(),
+        start_time: overall_start_time, // Overall start time for timeout checks
+        weights: &weights,
+    };
+
     let mut best_move = *legal_moves.first().unwrap_or(&Move::Down); // Default to first safe or down
     let mut best_score = i32::MIN;
 
     // Iterate through our first set of moves
     for &move_option in &legal_moves {
         let move_start_time = Instant::now();
-        let next_sim_state = simulate_turn_with_heuristic_opponents(&sim_state_initial, our_id, move_option);
-        
         let score = minimax(
-            next_sim_state,
-            depth - 1,
+            sim_state_initial.clone(),
+            depth.saturating_sub(1),
+            1,
             i32::MIN,
             i32::MAX,
-            false, // Opponent's turn next
-            our_id,
-            overall_start_time, // Pass overall start time for timeout check
+            Some(move_option), // Opponents reply, moving together with us
+            &ctx,
         );
 
         let move_duration = move_start_time.elapsed();
@@ -67,64 +84,35 @@ pub fn minimax_search(state: &GameState, depth: u8) -> Option<Move> {
 }
 
 // --- Minimax Recursive Helper ---
+// `ply` counts plies below the search root so terminal scores can prefer quick wins and slow losses.
+// With `pending_move` None it is our turn to choose (maximizing). Otherwise the opponents reply to
+// our pending move, and both are applied together as one engine turn.
 fn minimax(
     state: SimState,
     depth: u8,
+    ply: u32,
     mut alpha: i32,
-    mut beta: i32,
-    is_maximizing_player: bool,
-    our_id: &str,
-    start_time: Instant,
+    beta: i32,
+    pending_move: Option<Move>,
+    ctx: &SearchContext,
 ) -> i32 {
+    let our_id = ctx.our_id;
+    let evaluate = |state: &SimState| evaluation::evaluate_sim_state(state, our_id, ply, ctx.root_opponents, ctx.weights);
+
     // Check time limit first
-    let elapsed = start_time.elapsed();
+    let elapsed = ctx.start_time.elapsed();
     if elapsed.as_millis() > MAX_SEARCH_TIME_MS {
         warn!("Timeout hit inside minimax recursion at depth {}. Returning eval.", depth);
-        return evaluation::evaluate_sim_state(&state, our_id); 
+        return evaluate(&state);
     }
     
-    // Base Case: Leaf node (depth 0 or terminal state)
-    if depth == 0 || state.snakes.len() <= 1 || state.snakes.iter().all(|s| s.health == 0) {
-        return evaluation::evaluate_sim_state(&state, our_id);
-    }
-    let current_snake_turn_id = if is_maximizing_player { our_id.to_string() } else { 
-        // Simplification: Assume minimizer controls the *next* opponent snake in the list? 
-        // Or just evaluate based on the state after *all* opponents move heuristically?
-        // Let's stick with the latter for now.
-        // We need the state *after* opponents make their move below.
-         state.snakes.iter().find(|s| s.id != our_id).map(|s| s.id.clone()).unwrap_or_default()
-    };
-    if current_snake_turn_id.is_empty() && !is_maximizing_player { // Only our snake left? 
-         return evaluation::evaluate_sim_state(&state, our_id); // Should be caught by snakes.len() <= 1, but safe check.
-    }
-
-    if is_maximizing_player {
-        // Our turn (Maximizing)
-        let mut max_eval = i32::MIN;
-        let legal_moves = get_sim_safe_moves(&state, our_id);
-        if legal_moves.is_empty() {
-            return evaluation::evaluate_sim_state(&state, our_id); // Evaluate state if we have no moves
-        }
-
-        for &move_option in &legal_moves {
-            let next_sim_state = simulate_turn_with_heuristic_opponents(&state, our_id, move_option);
-            let eval = minimax(next_sim_state, depth - 1, alpha, beta, false, our_id, start_time);
-            max_eval = max_eval.max(eval);
-            alpha = alpha.max(eval);
-            if beta <= alpha {
-                break; // Beta cutoff
-            }
-        }
-        max_eval
-    } else {
+    if let Some(our_move) = pending_move {
         // Opponent's turn (Minimizing) - Assume they play heuristically
         // Note: This isn't true minimax, but a heuristic search.
-        // The state passed here *should* be the result of our previous move.
-        // We now simulate the opponents playing their *best* heuristic move.
-        let opponent_moves = predict_opponent_moves_heuristic(&state, our_id);
-        let next_state_after_opponents = state.apply_moves(&opponent_moves); 
-
-        minimax(next_state_after_opponents, depth - 1, alpha, beta, true, our_id, start_time)
+        // `state` is the position before our pending move; the opponents pick their *best*
+        // heuristic moves from it and the whole turn is applied at once.
+        let next_state = simulate_turn_with_heuristic_opponents(&state, our_id, our_move);
+        minimax(next_state, depth.saturating_sub(1), ply + 1, alpha, beta, None, ctx)
         
         // --- True Minimax (More Complex) requires iterating opponent moves --- 
         /*
@@ -156,12 +144,36 @@ fn minimax(
         }
         min_eval
         */
+    } else {
+        // Our turn (Maximizing)
+        // Base Case: Leaf node (depth 0 or terminal state: we are dead, or no opponents are left)
+        let we_are_alive = state.snakes.iter().any(|s| s.id == our_id);
+        if depth == 0 || !we_are_alive || state.opponents(our_id).next().is_none() || state.snakes.iter().all(|s| s.health == 0) {
+            return evaluate(&state);
+        }
+
+        let mut max_eval = i32::MIN;
+        let legal_moves = get_sim_safe_moves(&state, our_id);
+        if legal_moves.is_empty() {
+            return evaluate(&state); // Evaluate state if we have no moves
+        }
+
+        for &move_option in &legal_moves {
+            let eval = minimax(state.clone(), depth - 1, ply + 1, alpha, beta, Some(move_option), ctx);
+            max_eval = max_eval.max(eval);
+            alpha = alpha.max(eval);
+            if beta <= alpha {
+                break; // Beta cutoff
+            }
+        }
+        max_eval
     }
 }
 
 // --- Opponent Move Prediction Helper ---
 
-// Simple heuristic: Opponents choose their move maximizing their own flood fill space.
+// Simple heuristic: Opponents choose their move maximizing their own flood fill space. Squadmates
+// are predicted the same way: they play for themselves rather than against us.
 fn predict_opponent_moves_heuristic(state: &SimState, our_id: &str) -> HashMap<String, Move> {
     let mut opponent_moves = HashMap::new();
     for snake in &state.snakes {
@@ -178,10 +190,9 @@ fn predict_opponent_moves_heuristic(state: &SimState, our_id: &str) -> HashMap<S
         let mut best_opp_score = 0; // Flood fill space
 
         for &opp_move in &legal_moves {
-            if let Some(head) = snake.head() {
-                 let target = head.apply_move(opp_move);
+            if let Some(target) = snake.head().and_then(|head| state.step(head, opp_move)) {
                  // Evaluate based on flood fill from the target square
-                 let space = flood_fill_sim(state, &target);
+                 let space = flood_fill_sim(state, &snake.id, &target, 1);
                  if space > best_opp_score {
                      best_opp_score = space;
                      best_opp_move = opp_move;
@@ -198,4 +209,25 @@ fn simulate_turn_with_heuristic_opponents(state: &SimState, our_id: &str, our_mo
     let mut moves_for_turn = predict_opponent_moves_heuristic(state, our_id);
     moves_for_turn.insert(our_id.to_string(), our_move);
     state.apply_moves(&moves_for_turn)
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    #[test]
+    fn opponents_reply_in_the_same_turn_as_our_move() {
+        // Running left along the top edge is safe; our snake must not be left to default to Up
+        // while the opponents move.
+        let state = SimState::empty(11, 11).with_snakes(vec![
+            SimSnake::new("me", &[(5, 10), (5, 9), (5, 8)]),
+            SimSnake::new("them", &[(2, 0), (1, 0), (0, 0)]),
+        ]);
+        let weights = EvalWeights::default();
+        let ctx = SearchContext { our_id: "me", root_opponents: 1, start_time: Instant::now(), weights: &weights };
+
+        let score = minimax(state, 3, 1, i32::MIN, i32::MAX, Some(Move::Left), &ctx);
+        assert!(score > evaluation::DRAW_SCORE, "score {}", score);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::game_state::{Coord, Move};
+use crate::sim::state::{SimSnake, SimState};
+use super::safe_move::{MoveSafety, SafetyTier};
+use log::debug;
+use std::collections::HashMap;
+
+// Turns of lookahead when the time budget is unknown. `SURVIVAL_DEPTH` overrides the depth chosen
+// from the game's timeout.
+pub const DEFAULT_SURVIVAL_DEPTH: u32 = 3;
+const SURVIVAL_DEPTH_ENV: &str = "SURVIVAL_DEPTH";
+const MS_PER_TURN: u32 = 150; // Budget per turn of lookahead, in milliseconds of move timeout
+const MIN_DEPTH: u32 = 2;
+const MAX_DEPTH: u32 = 6;
+
+// How many turns ahead to check survival with `timeout_ms` to answer a move request.
+pub fn depth_for_budget(timeout_ms: u32) -> u32 {
+    std::env::var(SURVIVAL_DEPTH_ENV)
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .unwrap_or_else(|| (timeout_ms / MS_PER_TURN).clamp(MIN_DEPTH, MAX_DEPTH))
+}
+
+// The moves among `moves` that let snake `our_id` live longest, up to `depth` turns, if opponents
+// ignore us. Opponents' bodies are assumed to advance conservatively: each may eat once, so its
+// segments stay one turn longer than they otherwise would. When every move survives (or every
+// move dies equally soon) all of `moves` are kept.
+pub fn survivable_moves(state: &SimState, our_id: &str, moves: &[Move], depth: u32) -> Vec<Move> {
+    let you = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s,
+        None => return moves.to_vec(),
+    };
+    let obstacles = opponent_obstacles(state, our_id);
+
+    let turns: Vec<(Move, u32)> = moves.iter()
+        .map(|&m| (m, turns_survived(state, &obstacles, you.clone(), m, depth)))
+        .collect();
+    let longest = turns.iter().map(|&(_, t)| t).max().unwrap_or(0);
+    if longest < depth {
+        debug!("Survival: every move dies within {} turns: {:?}", depth, turns);
+    }
+    turns.into_iter().filter(|&(_, t)| t == longest).map(|(m, _)| m).collect()
+}
+
+// The classified moves, Deadly ones aside, that let snake `our_id` live longest (see
+// survivable_moves). Run before picking a safety tier, so a move that dies within `depth` turns
+// gives way to a worse tier that lives.
+pub fn drop_dying_moves(state: &SimState, our_id: &str, classified: &[MoveSafety], depth: u32) -> Vec<MoveSafety> {
+    let candidates: Vec<Move> = classified.iter().filter(|m| m.tier > SafetyTier::Deadly).map(|m| m.direction).collect();
+    let survivors = survivable_moves(state, our_id, &candidates, depth);
+    classified.iter().filter(|m| survivors.contains(&m.direction)).cloned().collect()
+}
+
+// Turns until each square occupied by an opponent is vacated, allowing for one growth.
+fn opponent_obstacles(state: &SimState, our_id: &str) -> HashMap<Coord, u32> {
+    let tails_vacate = state.rules.tails_vacate();
+    let mut obstacles: HashMap<Coord, u32> = HashMap::new();
+    let you = state.snakes.iter().find(|s| s.id == our_id);
+    for snake in state.snakes.iter().filter(|s| s.id != our_id && !you.is_some_and(|y| state.can_pass_through(y, s))) {
+        let n = snake.body.len() as u32;
+        for (i, segment) in snake.body.iter().enumerate() {
+            let turns = if tails_vacate { n - i as u32 + 1 } else { u32::MAX };
+            let entry = obstacles.entry(*segment).or_insert(turns);
+            *entry = (*entry).max(turns);
+        }
+    }
+    obstacles
+}
+
+// How many turns, up to `depth`, `snake` lives after making move `m` and then its best moves.
+// `state` is the position before the move; its food, hazards and rules apply throughout.
+fn turns_survived(state: &SimState, obstacles: &HashMap<Coord, u32>, snake: SimSnake, m: Move, depth: u32) -> u32 {
+    let mut eaten = Vec::new();
+    survive(state, obstacles, snake, m, 1, depth, &mut eaten)
+}
+
+fn survive(
+    state: &SimState,
+    obstacles: &HashMap<Coord, u32>,
+    mut snake: SimSnake,
+    m: Move,
+    turn: u32,
+    depth: u32,
+    eaten: &mut Vec<Coord>,
+) -> u32 {
+    let target = match snake.head().and_then(|h| state.step(h, m)) {
+        Some(t) => t,
+        None => return turn - 1, // Wall
+    };
+    if obstacles.get(&target).is_some_and(|&vacated| turn < vacated) {
+        return turn - 1;
+    }
+
+    // Health and growth, as in SimState::apply_moves.
+    let ate = state.food.contains(&target) && !eaten.contains(&target);
+    let damage = 1 + if state.hazards.contains(&target) { state.rules.hazard_damage } else { 0 };
+    snake.health = if ate || !state.rules.snakes_starve() { 100 } else { snake.health.saturating_sub(damage) };
+    if snake.health == 0 {
+        return turn - 1;
+    }
+    snake.advance(target, ate || state.rules.grows_every_turn());
+    if snake.body.iter().skip(1).any(|segment| *segment == target) {
+        return turn - 1; // Own body
+    }
+    if turn >= depth {
+        return turn;
+    }
+
+    if ate {
+        eaten.push(target);
+    }
+    let mut best = turn;
+    for next in [Move::Up, Move::Down, Move::Left, Move::Right] {
+        best = best.max(survive(state, obstacles, snake.clone(), next, turn + 1, depth, eaten));
+        if best >= depth {
+            break;
+        }
+    }
+    if ate {
+        eaten.pop();
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::head_to_head::RiskTier;
+    use crate::logic::safe_move::best_moves;
+
+    fn board(snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(7, 7).with_snakes(snakes)
+    }
+
+    #[test]
+    fn a_dead_end_shorter_than_the_lookahead_is_dropped() {
+        // Down leads to the corner (0, 0), shut by "them"'s head at (1, 0); up is open.
+        let state = board(vec![
+            SimSnake::new("me", &[(0, 1), (1, 1), (2, 1)]),
+            SimSnake::new("them", &[(1, 0), (2, 0), (3, 0)]),
+        ]);
+        assert_eq!(survivable_moves(&state, "me", &[Move::Up, Move::Down], 3), vec![Move::Up]);
+        // One turn ahead the corner is as good as anywhere.
+        assert_eq!(survivable_moves(&state, "me", &[Move::Up, Move::Down], 1), vec![Move::Up, Move::Down]);
+    }
+
+    #[test]
+    fn dying_moves_give_way_to_a_worse_tier() {
+        let state = board(vec![
+            SimSnake::new("me", &[(0, 1), (1, 1), (2, 1)]),
+            SimSnake::new("them", &[(1, 0), (2, 0), (3, 0)]),
+        ]);
+        let classified = |direction, tier| MoveSafety { direction, tier, reasons: Vec::new(), head_to_head: RiskTier::Safe };
+        let moves = [classified(Move::Up, SafetyTier::Risky), classified(Move::Down, SafetyTier::Safe), classified(Move::Left, SafetyTier::Deadly)];
+        let surviving = drop_dying_moves(&state, "me", &moves, 3);
+        assert_eq!(surviving, vec![moves[0].clone()]);
+        assert_eq!(best_moves(&surviving), vec![Move::Up]);
+    }
+
+    #[test]
+    fn moves_that_all_survive_are_kept() {
+        let state = board(vec![
+            SimSnake::new("me", &[(3, 3), (3, 2), (3, 1)]),
+            SimSnake::new("them", &[(5, 5), (5, 4), (5, 3)]),
+        ]);
+        let moves = [Move::Up, Move::Left, Move::Right];
+        assert_eq!(survivable_moves(&state, "me", &moves, 3), moves.to_vec());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::game_state::{Coord, Move};
+use crate::sim::state::SimState;
+use super::flood_fill;
+use super::head_to_head;
+use super::safe_move::get_sim_safe_moves;
+use log::debug;
+
+// Payoffs, in units of one opponent eliminated = KILL_PAYOFF.
+const KILL_PAYOFF: i32 = 100;
+const SEAL_CONFIDENCE: f32 = 0.8; // A sealed snake can still be freed by a third snake's tail
+const PIN_PAYOFF: i32 = 30;        // Keeping a snake pinned to a wall, at the wall's far end
+const MAX_LEAD: i32 = 2;           // How far ahead of a pinned snake we can be and still hold it
+
+// Payoff at which a tactical move is worth taking without searching.
+pub const DECISIVE_PAYOFF: i32 = 75;
+
+// Kind of opportunity a tactical move exploits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tactic {
+    HeadToHead, // Meet a shorter snake head-on on a square it is likely (or forced) to move to
+    Seal,       // Close off the region a snake is in while it is smaller than the snake
+    WallCutoff, // Run alongside a snake travelling down a wall so it cannot turn away from it
+}
+
+// A move that attacks `target`, with its expected payoff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TacticalMove {
+    pub our_move: Move,
+    pub tactic: Tactic,
+    pub target: String,
+    pub payoff: i32,
+}
+
+// Looks for attacking moves for snake `our_id` among `our_moves`, best payoff first.
+pub fn find_tactics(state: &SimState, our_id: &str, our_moves: &[Move]) -> Vec<TacticalMove> {
+    let you = match state.snakes.iter().find(|s| s.id == our_id) {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    let head = match you.head() {
+        Some(h) => *h,
+        None => return Vec::new(),
+    };
+
+    let mut tactics = Vec::new();
+    for &our_move in our_moves {
+        let target = match state.step(&head, our_move) {
+            Some(t) => t,
+            None => continue,
+        };
+        let after = advance(state, our_id, our_move);
+        // Never trade our own room for an attack.
+        let has_room = |s: &SimState| flood_fill::flood_fill_sim(s, our_id, &target, 0) >= you.length();
+
+        let h2h = head_to_head::advantageous_head_to_head(state, our_id, &target);
+        let victim = state.opponents(our_id)
+            .filter(|s| s.length() < you.length())
+            .find(|s| s.head().is_some_and(|h| h.manhattan_distance(&target) == 1));
+        if let (true, Some(victim)) = (h2h > 0.0, victim) {
+            let mut won = after.clone();
+            won.snakes.retain(|s| s.id != victim.id);
+            if has_room(&won) {
+                tactics.push(TacticalMove {
+                    our_move,
+                    tactic: Tactic::HeadToHead,
+                    target: victim.id.clone(),
+                    payoff: (KILL_PAYOFF as f32 * h2h).round() as i32,
+                });
+            }
+        }
+
+        if !has_room(&after) {
+            continue;
+        }
+        for opponent in state.opponents(our_id) {
+            if let Some(payoff) = seal_payoff(state, &after, &opponent.id) {
+                tactics.push(TacticalMove { our_move, tactic: Tactic::Seal, target: opponent.id.clone(), payoff });
+            }
+            if let Some(payoff) = wall_cutoff_payoff(state, our_id, our_move, &opponent.id) {
+                tactics.push(TacticalMove { our_move, tactic: Tactic::WallCutoff, target: opponent.id.clone(), payoff });
+            }
+        }
+    }
+
+    tactics.sort_by_key(|t| std::cmp::Reverse(t.payoff));
+    if !tactics.is_empty() {
+        debug!("Tactics for {}: {:?}", our_id, tactics);
+    }
+    tactics
+}
+
+// `state` with only snake `snake_id` moved, everyone else standing still.
+fn advance(state: &SimState, snake_id: &str, m: Move) -> SimState {
+    let mut after = state.clone();
+    if let Some(snake) = after.snakes.iter_mut().find(|s| s.id == snake_id) {
+        if let Some(next_head) = snake.head().and_then(|h| state.step(h, m)) {
+            snake.advance(next_head, after.food.remove(&next_head));
+        }
+    }
+    after
+}
+
+// Most space snake `snake_id` can reach with any of its moves.
+fn best_space(state: &SimState, snake_id: &str) -> usize {
+    let head = match state.snakes.iter().find(|s| s.id == snake_id).and_then(|s| s.head()) {
+        Some(h) => *h,
+        None => return 0,
+    };
+    get_sim_safe_moves(state, snake_id).into_iter()
+        .filter_map(|m| state.step(&head, m))
+        .map(|target| flood_fill::flood_fill_sim(state, snake_id, &target, 1))
+        .max()
+        .unwrap_or(0)
+}
+
+// Payoff for leaving snake `opponent_id` (which had room before) with less space than its length.
+fn seal_payoff(before: &SimState, after: &SimState, opponent_id: &str) -> Option<i32> {
+    let length = before.snakes.iter().find(|s| s.id == opponent_id)?.length();
+    if best_space(before, opponent_id) < length {
+        return None; // Already trapped, not our doing
+    }
+    let space = best_space(after, opponent_id);
+    if space >= length {
+        return None;
+    }
+    // The smaller the pocket, the surer the kill.
+    let sureness = SEAL_CONFIDENCE * (1.0 - space as f32 / length as f32).max(0.5);
+    Some((KILL_PAYOFF as f32 * sureness).round() as i32)
+}
+
+// Payoff for moving alongside snake `opponent_id` while it runs down a wall, level with or ahead of
+// it, so its only ways on are straight ahead or into us. Worth more the nearer the corner is.
+fn wall_cutoff_payoff(state: &SimState, our_id: &str, our_move: Move, opponent_id: &str) -> Option<i32> {
+    let you = state.snakes.iter().find(|s| s.id == our_id)?;
+    let opponent = state.snakes.iter().find(|s| s.id == opponent_id)?;
+    let (our_head, their_head, their_neck) = (*you.head()?, *opponent.head()?, *opponent.body.get(1)?);
+    let heading = state.direction_to(&their_neck, &their_head)?;
+    if our_move != heading {
+        return None;
+    }
+
+    // Unit step along the heading, and the wall the opponent is pinned against: the side on which
+    // it cannot step off the board. Wrapped boards have no walls.
+    let unit = Coord { x: 0, y: 0 }.apply_move(heading);
+    let (dx, dy) = (unit.x, unit.y);
+    let sides = match heading {
+        Move::Up | Move::Down => [Move::Left, Move::Right],
+        Move::Left | Move::Right => [Move::Up, Move::Down],
+    };
+    let wall_side = sides.into_iter().find(|&side| state.step(&their_head, side).is_none())?;
+    let wall_normal = Coord { x: 0, y: 0 }.apply_move(wall_side);
+
+    // We must be on the inner side, directly beside its line, and not behind it.
+    let beside = Coord { x: their_head.x - wall_normal.x, y: their_head.y - wall_normal.y };
+    let along = (our_head.x - beside.x) * dx + (our_head.y - beside.y) * dy;
+    let off_line = (our_head.x - beside.x) * dy.abs() + (our_head.y - beside.y) * dx.abs();
+    if off_line != 0 || !(0..=MAX_LEAD).contains(&along) {
+        return None;
+    }
+
+    // Squares left before the corner.
+    let mut run = 0;
+    let mut at = their_head;
+    while let Some(next) = state.step(&at, heading) {
+        at = next;
+        run += 1;
+    }
+    let span = if dx != 0 { state.width } else { state.height };
+    Some(PIN_PAYOFF * (span - run) / span.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    fn board(ruleset: &str, size: i32, snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(size, size).with_ruleset(ruleset).with_snakes(snakes)
+    }
+
+    const ALL_MOVES: [Move; 4] = [Move::Up, Move::Down, Move::Left, Move::Right];
+
+    fn find(tactics: &[TacticalMove], tactic: Tactic, our_move: Move) -> Option<&TacticalMove> {
+        tactics.iter().find(|t| t.tactic == tactic && t.our_move == our_move && t.target == "them")
+    }
+
+    #[test]
+    fn a_shorter_snake_forced_into_us_is_a_kill() {
+        // Cornered at (0, 6), "them" can only move down to (0, 5), where we meet it.
+        let state = board("standard", 7, vec![
+            SimSnake::new("me", &[(0, 4), (0, 3), (0, 2), (0, 1)]),
+            SimSnake::new("them", &[(0, 6), (1, 6), (1, 5)]),
+        ]);
+        let tactics = find_tactics(&state, "me", &ALL_MOVES);
+        let kill = find(&tactics, Tactic::HeadToHead, Move::Up).expect("a head-to-head");
+        assert_eq!(kill.payoff, KILL_PAYOFF);
+        assert_eq!(tactics[0], *kill);
+        assert!(kill.payoff >= DECISIVE_PAYOFF);
+    }
+
+    #[test]
+    fn closing_the_only_exit_seals_a_snake_in() {
+        // "them" heads left along the top edge above "wall"; its only way out is down the left
+        // edge past (0, 3). Taking (0, 3) leaves it three squares for a length of four: our body
+        // and the wall's head end stay put until it is too late.
+        let state = board("standard", 6, vec![
+            SimSnake::new("me", &[(1, 3), (2, 3), (3, 3), (4, 3), (5, 3)]),
+            SimSnake::new("them", &[(2, 5), (3, 5), (4, 5), (5, 5)]),
+            SimSnake::new("wall", &[(1, 4), (2, 4), (3, 4), (4, 4), (5, 4)]),
+        ]);
+        let tactics = find_tactics(&state, "me", &ALL_MOVES);
+        let seal = find(&tactics, Tactic::Seal, Move::Left).expect("a seal");
+        assert_eq!(seal.payoff, (KILL_PAYOFF as f32 * SEAL_CONFIDENCE * 0.5).round() as i32);
+        assert!(find(&tactics, Tactic::Seal, Move::Down).is_none());
+    }
+
+    #[test]
+    fn running_beside_a_snake_on_the_wall_pins_it() {
+        // "them" runs up the left wall; we run up beside it, three squares from the corner.
+        let snakes = vec![SimSnake::new("me", &[(1, 3), (1, 2), (1, 1)]), SimSnake::new("them", &[(0, 3), (0, 2), (0, 1)])];
+        let tactics = find_tactics(&board("standard", 7, snakes.clone()), "me", &ALL_MOVES);
+        let pin = find(&tactics, Tactic::WallCutoff, Move::Up).expect("a wall cut-off");
+        assert_eq!(pin.payoff, PIN_PAYOFF * (7 - 3) / 7);
+        assert!(find(&tactics, Tactic::WallCutoff, Move::Right).is_none());
+
+        // Wrapped boards have no walls to pin against.
+        let tactics = find_tactics(&board("wrapped", 7, snakes), "me", &ALL_MOVES);
+        assert!(find(&tactics, Tactic::WallCutoff, Move::Up).is_none());
+    }
+}
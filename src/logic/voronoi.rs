@@ -0,0 +1,125 @@
+use crate::game_state::Coord;
+use crate::sim::state::SimState;
+use super::flood_fill;
+use std::collections::{HashMap, HashSet};
+
+// Cells and food a single snake reaches strictly before every other snake.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Territory {
+    pub cells: usize,
+    pub food: usize,
+}
+
+// Result of a Voronoi partition of the free cells of a SimState.
+#[derive(Debug, Clone)]
+pub struct VoronoiPartition {
+    // Owner of every reached free cell as an index into `SimState::snakes`.
+    // `None` marks a contested cell (reached first by equal-length snakes at the same time).
+    pub owners: HashMap<Coord, Option<usize>>,
+    // Territory per snake, indexed like `SimState::snakes`.
+    pub territories: Vec<Territory>,
+}
+
+impl VoronoiPartition {
+    // Territory of the snake with the given ID, or an empty territory if it is not on the board.
+    pub fn territory_of(&self, state: &SimState, snake_id: &str) -> Territory {
+        state.snakes.iter()
+            .position(|s| s.id == snake_id)
+            .and_then(|i| self.territories.get(i).copied())
+            .unwrap_or_default()
+    }
+}
+
+// Partitions the free cells of `state` between snakes with a multi-source BFS from every head.
+// Each cell belongs to the snake that reaches it first; simultaneous arrivals go to the longest
+// snake, and a tie in length leaves the cell contested. Contested cells do not expand further.
+pub fn voronoi_partition(state: &SimState) -> VoronoiPartition {
+    let blocked = flood_fill::static_obstacles(state);
+
+    let mut owners: HashMap<Coord, Option<usize>> = HashMap::new();
+    let mut territories = vec![Territory::default(); state.snakes.len()];
+
+    let mut frontier: Vec<(Coord, usize)> = state.snakes.iter().enumerate()
+        .filter_map(|(i, snake)| snake.head().map(|head| (*head, i)))
+        .collect();
+    let mut seen: HashSet<Coord> = frontier.iter().map(|(head, _)| *head).collect();
+
+    while !frontier.is_empty() {
+        // Gather every snake that reaches each new cell at this distance.
+        let mut claims: HashMap<Coord, Vec<usize>> = HashMap::new();
+        for (cell, owner) in &frontier {
+            for neighbor in state.neighbours(cell) {
+                if blocked.contains(&neighbor) || seen.contains(&neighbor) {
+                    continue;
+                }
+                let claimants = claims.entry(neighbor).or_default();
+                if !claimants.contains(owner) {
+                    claimants.push(*owner);
+                }
+            }
+        }
+
+        let mut next_frontier = Vec::new();
+        for (cell, claimants) in claims {
+            seen.insert(cell);
+            let max_len = claimants.iter().map(|&i| state.snakes[i].length()).max().unwrap_or(0);
+            let mut longest = claimants.iter().filter(|&&i| state.snakes[i].length() == max_len);
+            let owner = match (longest.next(), longest.next()) {
+                (Some(&i), None) => Some(i),
+                _ => None, // Equal-length tie: nobody owns it
+            };
+
+            if let Some(i) = owner {
+                territories[i].cells += 1;
+                if state.food.contains(&cell) {
+                    territories[i].food += 1;
+                }
+                next_frontier.push((cell, i));
+            }
+            owners.insert(cell, owner);
+        }
+        frontier = next_frontier;
+    }
+
+    VoronoiPartition { owners, territories }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    // A corridor one square high.
+    fn corridor(ruleset: &str, width: i32, snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(width, 1).with_ruleset(ruleset).with_snakes(snakes)
+    }
+
+    #[test]
+    fn equal_snakes_split_the_middle_and_contest_it() {
+        let state = corridor("standard", 7, vec![SimSnake::new("a", &[(1, 0)]), SimSnake::new("b", &[(5, 0)])]).with_food(&[(0, 0)]);
+        let partition = voronoi_partition(&state);
+        assert_eq!(partition.territory_of(&state, "a"), Territory { cells: 2, food: 1 });
+        assert_eq!(partition.territory_of(&state, "b"), Territory { cells: 2, food: 0 });
+        assert_eq!(partition.owners[&Coord { x: 3, y: 0 }], None);
+        assert_eq!(partition.territory_of(&state, "nobody"), Territory::default());
+    }
+
+    #[test]
+    fn longer_snakes_win_ties_and_tails_move_away() {
+        // b's tail tip at (6, 0) is free next turn, and b is longer, so it takes (3, 0) as well.
+        let state = corridor("standard", 7, vec![SimSnake::new("a", &[(1, 0)]), SimSnake::new("b", &[(5, 0), (6, 0)])]);
+        let partition = voronoi_partition(&state);
+        assert_eq!(partition.territory_of(&state, "a").cells, 2);
+        assert_eq!(partition.territory_of(&state, "b").cells, 3);
+        assert_eq!(partition.owners[&Coord { x: 3, y: 0 }], Some(1));
+    }
+
+    #[test]
+    fn wrapped_territory_reaches_across_the_edge() {
+        let state = corridor("wrapped", 7, vec![SimSnake::new("a", &[(0, 0)]), SimSnake::new("b", &[(3, 0)])]);
+        let partition = voronoi_partition(&state);
+        assert_eq!(partition.owners[&Coord { x: 6, y: 0 }], Some(0));
+        assert_eq!(partition.owners[&Coord { x: 5, y: 0 }], None);
+        assert_eq!(partition.territory_of(&state, "a").cells, 2);
+    }
+}
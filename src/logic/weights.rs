@@ -0,0 +1,435 @@
+use super::evaluation::Feature;
+use crate::sim::state::SimState;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+
+// Environment variable naming a JSON weight file. The file maps ruleset names (or "default")
+// to weight profiles; anything left out keeps its built-in value.
+const WEIGHTS_FILE_ENV: &str = "EVAL_WEIGHTS_FILE";
+const DEFAULT_KEY: &str = "default";
+
+const FEATURE_COUNT: usize = Feature::ALL.len();
+
+// Weights as written in config files: feature name -> weight.
+type WeightMap = BTreeMap<String, f32>;
+
+fn weights_from_map(mut values: [f32; FEATURE_COUNT], map: WeightMap) -> [f32; FEATURE_COUNT] {
+    for (name, value) in map {
+        match Feature::from_name(&name) {
+            Some(feature) => values[feature as usize] = value,
+            None => warn!("Ignoring weight for unknown evaluation feature '{}'", name),
+        }
+    }
+    values
+}
+
+fn weights_to_map(values: &[f32; FEATURE_COUNT]) -> WeightMap {
+    Feature::ALL.iter().map(|f| (f.name().to_string(), values[*f as usize])).collect()
+}
+
+// Evaluation weights, one per evaluation feature. Features missing from a config file keep
+// their default weight.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(from = "WeightMap", into = "WeightMap")]
+pub struct EvalWeights([f32; FEATURE_COUNT]);
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights([
+            1.0,   // health
+            10.0,  // length
+            2.0,   // space
+            5.0,   // length_advantage
+            3.0,   // territory
+            -10.0, // trap
+            2.0,   // food_won
+            -4.0,  // hazard
+            2.0,   // chokepoints
+        ])
+    }
+}
+
+impl EvalWeights {
+    pub fn weight(&self, feature: Feature) -> f32 {
+        self.0[feature as usize]
+    }
+
+    pub fn set_weight(&mut self, feature: Feature, value: f32) {
+        self.0[feature as usize] = value;
+    }
+
+    // Linear interpolation towards `other`: t = 0 gives `self`, t = 1 gives `other`.
+    pub fn lerp(&self, other: &EvalWeights, t: f32) -> EvalWeights {
+        let mut values = self.0;
+        for (v, o) in values.iter_mut().zip(other.0.iter()) {
+            *v += (o - *v) * t;
+        }
+        EvalWeights(values)
+    }
+
+    // Adds `adjustment` scaled by `amount`.
+    pub fn adjusted(&self, adjustment: &WeightAdjustment, amount: f32) -> EvalWeights {
+        let mut values = self.0;
+        for (v, a) in values.iter_mut().zip(adjustment.0.iter()) {
+            *v += a * amount;
+        }
+        EvalWeights(values)
+    }
+}
+
+impl From<WeightMap> for EvalWeights {
+    fn from(map: WeightMap) -> Self {
+        EvalWeights(weights_from_map(EvalWeights::default().0, map))
+    }
+}
+
+impl From<EvalWeights> for WeightMap {
+    fn from(weights: EvalWeights) -> Self {
+        weights_to_map(&weights.0)
+    }
+}
+
+impl fmt::Debug for EvalWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(Feature::ALL.iter().map(|ft| (ft.name(), self.weight(*ft)))).finish()
+    }
+}
+
+// Per-feature change to weights, applied in proportion to how strongly a condition holds.
+// Features missing from a config file are left unchanged.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "WeightMap", into = "WeightMap")]
+pub struct WeightAdjustment([f32; FEATURE_COUNT]);
+
+impl From<WeightMap> for WeightAdjustment {
+    fn from(map: WeightMap) -> Self {
+        WeightAdjustment(weights_from_map([0.0; FEATURE_COUNT], map))
+    }
+}
+
+impl From<WeightAdjustment> for WeightMap {
+    fn from(adjustment: WeightAdjustment) -> Self {
+        weights_to_map(&adjustment.0)
+    }
+}
+
+impl fmt::Debug for WeightAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(Feature::ALL.iter().map(|ft| (ft.name(), self.0[*ft as usize]))).finish()
+    }
+}
+
+// Where the early, mid and late game weight sets apply.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhaseThresholds {
+    pub mid_turn: u32,      // Turn by which the mid-game weights fully apply
+    pub late_turn: u32,     // Turn by which the late-game weights fully apply
+    pub late_snakes: usize, // With this many snakes or fewer left, us and our opponents, the late game comes by mid_turn
+}
+
+impl Default for PhaseThresholds {
+    fn default() -> Self {
+        PhaseThresholds { mid_turn: 30, late_turn: 150, late_snakes: 2 }
+    }
+}
+
+// When the state-dependent adjustments reach full strength.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdjustmentThresholds {
+    pub scarce_food_per_snake: f32, // Scarcity ramps from 0 at this much food per snake to 1 with none
+    pub full_lead: u32,             // Length lead at which the leading adjustment fully applies
+}
+
+impl Default for AdjustmentThresholds {
+    fn default() -> Self {
+        AdjustmentThresholds { scarce_food_per_snake: 1.0, full_lead: 4 }
+    }
+}
+
+// Everything evaluation weights are derived from for one ruleset: weight sets for each game phase,
+// plus adjustments for hunger, food scarcity and a length lead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeightProfile {
+    pub early: EvalWeights,
+    pub mid: EvalWeights,
+    pub late: EvalWeights,
+    pub phases: PhaseThresholds,
+    pub hungry: WeightAdjustment,
+    pub scarce_food: WeightAdjustment,
+    pub leading: WeightAdjustment,
+    pub adjustments: AdjustmentThresholds,
+}
+
+impl Default for WeightProfile {
+    fn default() -> Self {
+        let weights_with = |overrides: &[(Feature, f32)]| {
+            let mut weights = EvalWeights::default();
+            for &(feature, value) in overrides {
+                weights.set_weight(feature, value);
+            }
+            weights
+        };
+        let adjustment_with = |changes: &[(Feature, f32)]| {
+            let mut adjustment = WeightAdjustment::default();
+            for &(feature, value) in changes {
+                adjustment.0[feature as usize] = value;
+            }
+            adjustment
+        };
+
+        WeightProfile {
+            // Early on, growing matters most; late, space and territory decide games.
+            early: weights_with(&[(Feature::Length, 12.0), (Feature::LengthAdvantage, 4.0), (Feature::Territory, 2.0)]),
+            mid: EvalWeights::default(),
+            late: weights_with(&[
+                (Feature::Length, 6.0),
+                (Feature::Space, 3.0),
+                (Feature::LengthAdvantage, 6.0),
+                (Feature::Territory, 5.0),
+                (Feature::Trap, -15.0),
+            ]),
+            phases: PhaseThresholds::default(),
+            hungry: adjustment_with(&[(Feature::Health, 3.0), (Feature::Length, 2.0), (Feature::FoodWon, 4.0)]),
+            scarce_food: adjustment_with(&[(Feature::Health, 1.0), (Feature::Territory, 1.0)]),
+            leading: adjustment_with(&[(Feature::Length, -4.0), (Feature::Territory, 2.0)]),
+            adjustments: AdjustmentThresholds::default(),
+        }
+    }
+}
+
+impl WeightProfile {
+    // The same weights in every phase, with no adjustments.
+    pub fn fixed(weights: EvalWeights) -> Self {
+        WeightProfile {
+            early: weights,
+            mid: weights,
+            late: weights,
+            hungry: WeightAdjustment::default(),
+            scarce_food: WeightAdjustment::default(),
+            leading: WeightAdjustment::default(),
+            ..WeightProfile::default()
+        }
+    }
+
+    // Game phase for snake `our_id` as a number from 0 (early) through 1 (mid) to 2 (late). The
+    // phase by turn number runs to mid at mid_turn and to late at late_turn. With late_snakes or
+    // fewer of us and our opponents left, the phase runs twice as fast and reaches late at
+    // mid_turn instead, so it never jumps as turns go by.
+    pub fn phase(&self, state: &SimState, our_id: &str) -> f32 {
+        let p = &self.phases;
+        let by_turn = if state.turn < p.mid_turn {
+            state.turn as f32 / p.mid_turn.max(1) as f32
+        } else {
+            let span = p.late_turn.saturating_sub(p.mid_turn).max(1) as f32;
+            (1.0 + (state.turn - p.mid_turn) as f32 / span).min(2.0)
+        };
+        let by_snakes = if state.opponents(our_id).count() < p.late_snakes {
+            (2.0 * state.turn as f32 / p.mid_turn.max(1) as f32).min(2.0)
+        } else {
+            0.0
+        };
+        by_turn.max(by_snakes)
+    }
+
+    // Weights for evaluating positions below `state` from snake `our_id`'s perspective. `hunger` is
+    // the urgency from the hunger policy (see hunger::HungerPolicy::assess) and scales the hungry
+    // adjustment.
+    pub fn weights_for(&self, state: &SimState, our_id: &str, hunger: f32) -> EvalWeights {
+        let phase = self.phase(state, our_id);
+        let mut weights = if phase <= 1.0 {
+            self.early.lerp(&self.mid, phase)
+        } else {
+            self.mid.lerp(&self.late, phase - 1.0)
+        };
+
+        let you = match state.snakes.iter().find(|s| s.id == our_id) {
+            Some(s) => s,
+            None => return weights,
+        };
+        let a = &self.adjustments;
+
+        weights = weights.adjusted(&self.hungry, hunger.clamp(0.0, 1.0));
+
+        if a.scarce_food_per_snake > 0.0 && !state.snakes.is_empty() {
+            let food_per_snake = state.food.len() as f32 / state.snakes.len() as f32;
+            let scarcity = (1.0 - food_per_snake / a.scarce_food_per_snake).clamp(0.0, 1.0);
+            weights = weights.adjusted(&self.scarce_food, scarcity);
+        }
+
+        let longest_opponent = state.opponents(our_id).map(|s| s.length()).max();
+        if let Some(longest) = longest_opponent {
+            let lead = you.length() as f32 - longest as f32;
+            let leading = (lead / a.full_lead.max(1) as f32).clamp(0.0, 1.0);
+            weights = weights.adjusted(&self.leading, leading);
+        }
+
+        weights
+    }
+}
+
+// A ruleset's entry in the weight file: a full profile, or a flat weight set used in every phase.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProfileEntry {
+    Profile(Box<WeightProfile>),
+    Fixed(EvalWeights),
+}
+
+// Built-in weight profile for a ruleset, used when no weight file overrides it.
+fn builtin_profile(ruleset: &str) -> WeightProfile {
+    match ruleset {
+        "constrictor" => constrictor_profile(),
+        _ => WeightProfile::default(),
+    }
+}
+
+// Constrictor: every snake grows every turn and nobody starves, so health, length and food mean
+// nothing. Games are won by holding more room than the others and cutting them off from theirs.
+fn constrictor_profile() -> WeightProfile {
+    let weights = |space: f32, territory: f32, trap: f32| {
+        let mut weights = EvalWeights::default();
+        for feature in [Feature::Health, Feature::Length, Feature::LengthAdvantage, Feature::FoodWon] {
+            weights.set_weight(feature, 0.0);
+        }
+        weights.set_weight(Feature::Space, space);
+        weights.set_weight(Feature::Territory, territory);
+        weights.set_weight(Feature::Trap, trap);
+        weights
+    };
+    WeightProfile {
+        // The board fills up fast, so territory counts from the start and room to live late.
+        early: weights(2.0, 6.0, -15.0),
+        mid: weights(3.0, 8.0, -20.0),
+        late: weights(5.0, 8.0, -25.0),
+        hungry: WeightAdjustment::default(),
+        scarce_food: WeightAdjustment::default(),
+        leading: WeightAdjustment::default(),
+        ..WeightProfile::default()
+    }
+}
+
+// Reads the weight file once per process. Errors are logged and leave the built-in weights in place.
+fn configured_profiles() -> &'static HashMap<String, WeightProfile> {
+    static CONFIGURED: OnceLock<HashMap<String, WeightProfile>> = OnceLock::new();
+    CONFIGURED.get_or_init(|| {
+        let path = match std::env::var(WEIGHTS_FILE_ENV) {
+            Ok(p) => p,
+            Err(_) => return HashMap::new(),
+        };
+        match load_weight_file(&path) {
+            Ok(profiles) => {
+                info!("Loaded evaluation weights for {:?} from {}", profiles.keys().collect::<Vec<_>>(), path);
+                profiles
+            }
+            Err(e) => {
+                warn!("Could not load evaluation weights from {}: {}. Using built-in weights.", path, e);
+                HashMap::new()
+            }
+        }
+    })
+}
+
+// Reads a weight file: a JSON object mapping ruleset names (or "default") to weight profiles
+// or flat weight sets.
+pub fn load_weight_file(path: &str) -> Result<HashMap<String, WeightProfile>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_weight_file(&contents)
+}
+
+fn parse_weight_file(contents: &str) -> Result<HashMap<String, WeightProfile>, String> {
+    let entries: HashMap<String, ProfileEntry> = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    Ok(entries.into_iter().map(|(ruleset, entry)| {
+        let profile = match entry {
+            ProfileEntry::Profile(profile) => *profile,
+            ProfileEntry::Fixed(weights) => WeightProfile::fixed(weights),
+        };
+        (ruleset, profile)
+    }).collect())
+}
+
+// Weight profile for `ruleset`: the weight file's entry for the ruleset, then its "default" entry,
+// then the built-in profile.
+pub fn profile_for_ruleset(ruleset: &str) -> WeightProfile {
+    let configured = configured_profiles();
+    configured.get(ruleset)
+        .or_else(|| configured.get(DEFAULT_KEY))
+        .cloned()
+        .unwrap_or_else(|| builtin_profile(ruleset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::state::SimSnake;
+
+    #[test]
+    fn weight_files_override_only_what_they_name() {
+        let profiles = parse_weight_file(r#"{
+            "default": {"space": 4.0, "no_such_feature": 1.0},
+            "royale": {"mid": {"trap": -20.0}, "phases": {"mid_turn": 40}}
+        }"#).unwrap();
+
+        // A flat weight set applies in every phase; unknown feature names are ignored.
+        let default = &profiles["default"];
+        assert_eq!(default.early.weight(Feature::Space), 4.0);
+        assert_eq!(default.late.weight(Feature::Length), EvalWeights::default().weight(Feature::Length));
+        assert_eq!(default.hungry, WeightAdjustment::default());
+
+        let royale = &profiles["royale"];
+        assert_eq!(royale.mid.weight(Feature::Trap), -20.0);
+        assert_eq!(royale.mid.weight(Feature::Space), EvalWeights::default().weight(Feature::Space));
+        assert_eq!(royale.early, WeightProfile::default().early);
+        assert_eq!(royale.phases, PhaseThresholds { mid_turn: 40, ..PhaseThresholds::default() });
+    }
+
+    #[test]
+    fn misspelt_profile_fields_are_rejected() {
+        // Without deny_unknown_fields this would quietly load as a default profile.
+        assert!(parse_weight_file(r#"{"default": {"mid": {"space": 3.0}, "lates": {"space": 5.0}}}"#).is_err());
+        assert!(parse_weight_file(r#"{"default": {"space": "lots"}}"#).is_err());
+    }
+
+    #[test]
+    fn early_weights_blend_into_mid_weights() {
+        let profile = WeightProfile::default();
+        let snakes = |n: usize| (0..n).map(|i| SimSnake::new(&i.to_string(), &[(i as i32, 0)])).collect();
+        let mut state = SimState::empty(11, 11).with_snakes(snakes(4));
+        let length = |state: &SimState| profile.weights_for(state, "0", 0.0).weight(Feature::Length);
+
+        assert_eq!(length(&state), profile.early.weight(Feature::Length));
+        state.turn = profile.phases.mid_turn / 2;
+        let halfway = (profile.early.weight(Feature::Length) + profile.mid.weight(Feature::Length)) / 2.0;
+        assert!((length(&state) - halfway).abs() < 1e-4);
+        state.turn = profile.phases.mid_turn;
+        assert_eq!(length(&state), profile.mid.weight(Feature::Length));
+        state.turn = profile.phases.late_turn;
+        assert_eq!(length(&state), profile.late.weight(Feature::Length));
+    }
+
+    #[test]
+    fn duels_reach_the_late_game_without_jumping() {
+        let profile = WeightProfile::default();
+        let duel = SimState::empty(11, 11).with_snakes(vec![SimSnake::new("me", &[(1, 1)]), SimSnake::new("them", &[(9, 9)])]);
+        let phases: Vec<f32> = (0..60).map(|turn| profile.phase(&SimState { turn, ..duel.clone() }, "me")).collect();
+
+        assert_eq!(phases[0], 0.0);
+        assert_eq!(phases[profile.phases.mid_turn as usize], 2.0);
+        let step = 2.0 / profile.phases.mid_turn as f32 + 1e-4;
+        assert!(phases.windows(2).all(|pair| pair[1] >= pair[0] && pair[1] - pair[0] <= step));
+
+        // Squadmates are not opponents: two against two is not a duel.
+        let squads = SimState::empty(11, 11).with_ruleset("squad").with_snakes(vec![
+            SimSnake::new("me", &[(1, 1)]).with_squad("red"),
+            SimSnake::new("mate", &[(1, 9)]).with_squad("red"),
+            SimSnake::new("them", &[(9, 9)]).with_squad("blue"),
+            SimSnake::new("other", &[(9, 1)]).with_squad("blue"),
+        ]);
+        assert_eq!(profile.phase(&SimState { turn: profile.phases.mid_turn, ..squads }, "me"), 1.0);
+    }
+}
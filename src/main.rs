@@ -1,62 +1,9 @@
 use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
 use log::{info, error};
-use rand::seq::SliceRandom;
 use serde::Serialize;
 
-mod game_state;
-mod logic;
-mod sim;
-
-use game_state::{GameState, Move};
-
-// ---------------------------
-// Data structures
-// ---------------------------
-#[derive(Serialize, Debug)]
-pub struct Game {
-    pub id: String,
-    pub ruleset: Ruleset,
-    pub timeout: u32,
-}
-
-#[derive(Serialize, Debug)]
-pub struct Ruleset {
-    pub name: String,
-    pub version: String,
-}
-
-#[derive(Serialize, Debug, Clone)]
-pub struct Coord {
-    pub x: i32,
-    pub y: i32,
-}
-
-#[derive(Serialize, Debug, Clone)]
-pub struct Battlesnake {
-    pub id: String,
-    pub name: String,
-    pub health: u32,
-    pub body: Vec<Coord>,
-    pub head: Coord,
-    pub length: u32,
-}
-
-#[derive(Serialize, Debug)]
-pub struct Board {
-    pub height: i32,
-    pub width: i32,
-    pub food: Vec<Coord>,
-    pub hazards: Vec<Coord>,
-    pub snakes: Vec<Battlesnake>,
-}
-
-#[derive(Serialize, Debug)]
-pub struct GameState {
-    pub game: Game,
-    pub turn: u32,
-    pub board: Board,
-    pub you: Battlesnake,
-}
+use battlesnake_rst::game_state::{GameState, Move};
+use battlesnake_rst::logic;
 
 // ---------------------------
 // API responses
@@ -0,0 +1,445 @@
+use crate::game_state::{Coord, Game, GameState, Move, Ruleset, RulesetSettings};
+use crate::logic;
+use crate::logic::hunger::HungerPolicy;
+use crate::logic::safe_move::get_sim_safe_moves;
+use crate::logic::search::minimax_search_sim;
+use crate::logic::survival::{survivable_moves, DEFAULT_SURVIVAL_DEPTH};
+use crate::logic::weights::{self, WeightProfile};
+use super::food::{spawn_food, FoodSpawn};
+use super::maps::{FoodPlacement, HazardModel, MapRules};
+use super::rules::{GameMode, Rules};
+use super::state::{SimSnake, SimState};
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const START_HEALTH: u32 = 100;
+const START_LENGTH: usize = 3;
+const CONNECT_TIMEOUT_MS: u64 = 1000;
+const NETWORK_SLACK_MS: u64 = 100; // Allowed on top of the game timeout before a reply counts as late
+
+// Who picks the moves for one snake.
+#[derive(Debug, Clone)]
+pub enum Player {
+    Logic,                                                     // Our server's decide_move, in-process
+    Search { depth: u8, profile: Option<Box<WeightProfile>> }, // Minimax on the simulator; None uses the ruleset's profile
+    Random,                                                    // A random move among the safest
+    Http(String),                                              // A snake server, e.g. http://127.0.0.1:8080
+}
+
+impl Player {
+    // Parses "logic", "search", "search:<depth>", "search:<depth>:<weight file>", "random" or an
+    // http:// URL. A weight file's profile for `ruleset` is used, or else its default.
+    pub fn parse(spec: &str, ruleset: &str) -> Result<Player, String> {
+        match spec.split_once(':') {
+            _ if spec.starts_with("http://") => Ok(Player::Http(spec.trim_end_matches('/').to_string())),
+            None if spec == "logic" => Ok(Player::Logic),
+            None if spec == "random" => Ok(Player::Random),
+            None if spec == "search" => Ok(Player::Search { depth: 3, profile: None }),
+            Some(("search", rest)) => {
+                let (depth, path) = match rest.split_once(':') {
+                    Some((depth, path)) => (depth, Some(path)),
+                    None => (rest, None),
+                };
+                let depth = depth.parse().map_err(|e| format!("bad search depth '{}': {}", depth, e))?;
+                let profile = match path {
+                    Some(path) => {
+                        let profiles = weights::load_weight_file(path).map_err(|e| format!("{}: {}", path, e))?;
+                        let profile = profiles.get(ruleset).or_else(|| profiles.get("default")).cloned();
+                        Some(Box::new(profile.ok_or_else(|| format!("{} has no '{}' or default profile", path, ruleset))?))
+                    }
+                    None => None,
+                };
+                Ok(Player::Search { depth, profile })
+            }
+            _ => Err(format!("unknown snake '{}' (expected logic, search[:depth[:weights]], random or http://host:port)", spec)),
+        }
+    }
+}
+
+// Settings for a game played by the local engine.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub width: i32,
+    pub height: i32,
+    pub ruleset: String,
+    pub map: String,
+    pub settings: RulesetSettings,
+    pub seed: u64,
+    pub max_turns: u32,  // Games still running after this many turns are draws
+    pub timeout_ms: u32, // Sent to snakes as the move timeout; late HTTP replies are ignored
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            width: 11,
+            height: 11,
+            ruleset: "standard".to_string(),
+            map: "standard".to_string(),
+            settings: RulesetSettings::default(),
+            seed: 0,
+            max_turns: 1000,
+            timeout_ms: 500,
+        }
+    }
+}
+
+// How a game ended, by index into the players of `run_game`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOutcome {
+    Winner(usize),
+    Draw, // Everyone died on the same turn, or the turn limit was reached
+}
+
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub outcome: GameOutcome,
+    pub turns: u32,
+}
+
+// Snake ID used for the player at `index`.
+pub fn snake_id(index: usize) -> String {
+    format!("snake-{}", index)
+}
+
+fn player_index(id: &str) -> usize {
+    id.trim_start_matches("snake-").parse().unwrap_or(0)
+}
+
+// Start squares one in from the walls: the corners first, then the middle of each edge.
+pub fn spawn_points(width: i32, height: i32) -> Vec<Coord> {
+    let (left, right, bottom, top) = (1, width - 2, 1, height - 2);
+    let (mid_x, mid_y) = (width / 2, height / 2);
+    vec![
+        Coord { x: left, y: bottom },
+        Coord { x: right, y: top },
+        Coord { x: left, y: top },
+        Coord { x: right, y: bottom },
+        Coord { x: mid_x, y: bottom },
+        Coord { x: mid_x, y: top },
+        Coord { x: left, y: mid_y },
+        Coord { x: right, y: mid_y },
+    ]
+}
+
+// Plays one game between `players` the way the official engine runs it, and returns the outcome
+// by index into `players`. In squad games the winner is the first surviving squadmate. Fails on
+// maps whose layout the local engine cannot reproduce.
+pub fn run_game(config: &EngineConfig, players: &[Player]) -> Result<GameResult, String> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let game = Game {
+        id: format!("local-{}", config.seed),
+        ruleset: Ruleset { name: config.ruleset.clone(), version: "local".to_string(), settings: config.settings.clone() },
+        timeout: config.timeout_ms,
+        map: config.map.clone(),
+    };
+    let mut state = initial_state(config, players.len(), &mut rng)?;
+    let shrink_every = config.settings.royale.shrink_every_n_turns;
+    let royale = state.rules.mode == GameMode::Royale && shrink_every > 0;
+
+    for (i, player) in players.iter().enumerate() {
+        notify(player, "/start", &game, &state, &snake_id(i));
+    }
+
+    while state.turn < config.max_turns && !is_over(&state, players.len()) {
+        // Everyone decides at once, as they would against the real engine.
+        let seeds: Vec<u64> = state.snakes.iter().map(|_| rng.gen()).collect();
+        let moves: HashMap<String, Move> = std::thread::scope(|scope| {
+            let handles: Vec<_> = state.snakes.iter().zip(seeds).map(|(snake, seed)| {
+                let (game, state) = (&game, &state);
+                let player = &players[player_index(&snake.id)];
+                scope.spawn(move || (snake.id.clone(), choose_move(player, game, state, snake, seed)))
+            }).collect();
+            handles.into_iter().filter_map(|h| h.join().ok()).collect()
+        });
+        debug!("Turn {}: {:?}", state.turn, moves);
+
+        state = state.apply_moves(&moves);
+        if royale && state.turn.is_multiple_of(shrink_every) {
+            shrink_royale(&mut state, &mut rng);
+        }
+        spawn_food(&mut state, &mut rng);
+    }
+
+    for (i, player) in players.iter().enumerate() {
+        notify(player, "/end", &game, &state, &snake_id(i));
+    }
+
+    let outcome = match state.snakes.first() {
+        Some(winner) if players.len() > 1 && is_over(&state, players.len()) => GameOutcome::Winner(player_index(&winner.id)),
+        _ => GameOutcome::Draw,
+    };
+    Ok(GameResult { outcome, turns: state.turn })
+}
+
+// Turn 0: stacked snakes on shuffled spawn points, one item of food diagonally next to each and
+// one in the centre, then the ruleset's minimum. In squad games the seats alternate between two
+// squads.
+fn initial_state(config: &EngineConfig, snake_count: usize, rng: &mut StdRng) -> Result<SimState, String> {
+    let map = MapRules::known(&config.map).ok_or_else(|| format!("unknown map '{}'", config.map))?;
+    let hazards = map.initial_hazards(config.width, config.height)
+        .ok_or_else(|| format!("map '{}' has a hazard layout the local engine cannot draw", config.map))?;
+    if map.food == FoodPlacement::Fixed {
+        return Err(format!("map '{}' places food on squares the local engine does not know", config.map));
+    }
+
+    // Corners first, then edges, each in a random order.
+    let mut spawns = spawn_points(config.width, config.height);
+    spawns[..4].shuffle(rng);
+    spawns[4..].shuffle(rng);
+
+    let mut rules = Rules::from_ruleset(&config.ruleset, &config.settings).with_map(&config.map);
+    rules.shrink_every = 0; // The engine shrinks one side for real instead of projecting the rim
+    let squad_game = rules.squad.is_some();
+    let snakes: Vec<SimSnake> = spawns.into_iter().take(snake_count).enumerate().map(|(i, spawn)| SimSnake {
+        id: snake_id(i),
+        health: START_HEALTH,
+        body: std::iter::repeat_n(spawn, START_LENGTH).collect(),
+        squad: if squad_game { format!("squad-{}", i % 2) } else { String::new() },
+    }).collect();
+
+    let mut upcoming_hazards = VecDeque::new();
+    match rules.map.hazards {
+        HazardModel::Spiral { .. } => {
+            let start = Coord { x: rng.gen_range(0..config.width), y: rng.gen_range(0..config.height) };
+            upcoming_hazards = rules.map.hazard_layout(config.width, config.height, start, rng.gen_range(0..4), rng.gen());
+        }
+        HazardModel::Static(_) | HazardModel::None => {}
+    }
+
+    let mut state = SimState {
+        width: config.width,
+        height: config.height,
+        snakes,
+        food: HashSet::new(),
+        turn: 0,
+        hazards,
+        upcoming_hazards,
+        projected_hazards: HashMap::new(),
+        rules,
+        food_spawn: FoodSpawn::Off, // The engine spawns food itself, after each turn
+    };
+    if state.rules.minimum_food > 0 || state.rules.food_spawn_chance > 0 {
+        let heads: Vec<Coord> = state.snakes.iter().filter_map(|s| s.head().copied()).collect();
+        for head in heads {
+            let mut diagonals: Vec<Coord> = [(-1, -1), (-1, 1), (1, -1), (1, 1)].iter()
+                .map(|&(dx, dy)| Coord { x: head.x + dx, y: head.y + dy })
+                .filter(|c| state.in_bounds(c) && !state.food.contains(c))
+                .collect();
+            diagonals.shuffle(rng);
+            state.food.extend(diagonals.first());
+        }
+        state.food.insert(Coord { x: config.width / 2, y: config.height / 2 });
+    }
+    spawn_food(&mut state, rng);
+    Ok(state)
+}
+
+// Whether the game has ended: one snake (or one squad) left, or nobody in a solo game.
+fn is_over(state: &SimState, player_count: usize) -> bool {
+    if player_count <= 1 {
+        return state.snakes.is_empty();
+    }
+    match state.snakes.first() {
+        None => true,
+        Some(first) => state.snakes.iter().all(|s| s.id == first.id || state.are_allies(first, s)),
+    }
+}
+
+// Royale: one side of the safe zone, chosen at random, turns to hazard.
+fn shrink_royale(state: &mut SimState, rng: &mut StdRng) {
+    let (lo, hi) = match state.safe_zone() {
+        Some(zone) => zone,
+        None => return,
+    };
+    let side = rng.gen_range(0..4);
+    for x in lo.x..=hi.x {
+        for y in lo.y..=hi.y {
+            let on_side = match side {
+                0 => x == lo.x,
+                1 => x == hi.x,
+                2 => y == lo.y,
+                _ => y == hi.y,
+            };
+            if on_side {
+                state.hazards.insert(Coord { x, y });
+            }
+        }
+    }
+}
+
+// The move `player` makes for `snake`. Snakes that fail to answer in time carry on in the
+// direction they last moved, as they would on the real engine.
+fn choose_move(player: &Player, game: &Game, state: &SimState, snake: &SimSnake, seed: u64) -> Move {
+    let request = match state.to_api_state(game, &snake.id) {
+        Some(r) => r,
+        None => return Move::Up,
+    };
+    let chosen = match player {
+        Player::Logic => logic::decide_move(&request).ok(),
+        Player::Search { depth, profile } => {
+            // Search from what the snake is told, not from the engine's own state.
+            let view = SimState::from_api_state(&request);
+            let profile = profile.as_deref().cloned().unwrap_or_else(|| weights::profile_for_ruleset(&game.ruleset.name));
+            let hunger = HungerPolicy::for_ruleset(&game.ruleset.name).assess(&view, &snake.id).urgency;
+            let safe_moves = get_sim_safe_moves(&view, &snake.id);
+            let root_moves = survivable_moves(&view, &snake.id, &safe_moves, DEFAULT_SURVIVAL_DEPTH);
+            minimax_search_sim(&view, &snake.id, &root_moves, *depth, &profile, hunger).or_else(|| safe_moves.first().copied())
+        }
+        Player::Random => {
+            let safe_moves = get_sim_safe_moves(state, &snake.id);
+            safe_moves.choose(&mut StdRng::seed_from_u64(seed)).copied()
+        }
+        Player::Http(url) => ask_for_move(url, &request, game.timeout),
+    };
+    chosen.unwrap_or_else(|| {
+        let direction = match (snake.body.front(), snake.body.get(1)) {
+            (Some(head), Some(neck)) if head != neck => neck.direction_to(head),
+            _ => None,
+        };
+        direction.unwrap_or(Move::Up)
+    })
+}
+
+// POSTs /move to a snake server and reads its answer.
+fn ask_for_move(url: &str, request: &GameState, timeout_ms: u32) -> Option<Move> {
+    let body = serde_json::to_string(request).ok()?;
+    let reply = match http_post(url, "/move", &body, timeout_ms as u64 + NETWORK_SLACK_MS) {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("{} turn {}: no move from {}: {}", request.you.id, request.turn, url, e);
+            return None;
+        }
+    };
+    let reply: serde_json::Value = serde_json::from_str(&reply).ok()?;
+    match reply.get("move").and_then(|m| m.as_str()) {
+        Some("up") => Some(Move::Up),
+        Some("down") => Some(Move::Down),
+        Some("left") => Some(Move::Left),
+        Some("right") => Some(Move::Right),
+        other => {
+            warn!("{} turn {}: {} answered {:?}", request.you.id, request.turn, url, other);
+            None
+        }
+    }
+}
+
+// Sends /start or /end to HTTP snakes. Their answers are ignored.
+fn notify(player: &Player, path: &str, game: &Game, state: &SimState, id: &str) {
+    let url = match player {
+        Player::Http(url) => url,
+        _ => return,
+    };
+    // Eliminated snakes are told about the final board as if they were still on it.
+    let request = state.to_api_state(game, id).or_else(|| {
+        let mut with_you = state.clone();
+        with_you.snakes.push(SimSnake { id: id.to_string(), health: 0, body: VecDeque::new(), squad: String::new() });
+        with_you.to_api_state(game, id)
+    });
+    if let Some(body) = request.and_then(|r| serde_json::to_string(&r).ok()) {
+        if let Err(e) = http_post(url, path, &body, game.timeout as u64 + NETWORK_SLACK_MS) {
+            warn!("{} {}: {}", url, path, e);
+        }
+    }
+}
+
+// A minimal HTTP/1.1 client: one JSON POST per connection, returning the body of a 200 reply.
+fn http_post(url: &str, path: &str, body: &str, timeout_ms: u64) -> Result<String, String> {
+    let rest = url.strip_prefix("http://").ok_or("only http:// URLs are supported")?;
+    let (host, prefix) = rest.split_once('/').map(|(h, p)| (h, format!("/{}", p))).unwrap_or((rest, String::new()));
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+    let socket = address.to_socket_addrs().map_err(|e| e.to_string())?.next().ok_or("host not found")?;
+
+    let timeout = Duration::from_millis(timeout_ms);
+    let mut stream = TcpStream::connect_timeout(&socket, Duration::from_millis(CONNECT_TIMEOUT_MS)).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    write!(
+        stream,
+        "POST {}{} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        prefix, path, host, body.len(), body
+    ).map_err(|e| e.to_string())?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(|e| e.to_string())?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, content) = response.split_once("\r\n\r\n").ok_or("malformed response")?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("'{}'", status));
+    }
+    let chunked = head.lines().any(|l| {
+        let l = l.to_ascii_lowercase();
+        l.starts_with("transfer-encoding:") && l.contains("chunked")
+    });
+    Ok(if chunked { dechunk(content) } else { content.to_string() })
+}
+
+// Joins the chunks of a chunked transfer-encoded body.
+fn dechunk(mut content: &str) -> String {
+    let mut body = String::new();
+    while let Some((size, rest)) = content.split_once("\r\n") {
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16).unwrap_or(0);
+        if size == 0 || rest.len() < size {
+            break;
+        }
+        body.push_str(&rest[..size]);
+        content = rest[size..].trim_start_matches("\r\n");
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_games_are_reproducible_and_end() {
+        let config = EngineConfig { width: 7, height: 7, seed: 5, max_turns: 200, ..EngineConfig::default() };
+        let players = [Player::Random, Player::Random];
+        let first = run_game(&config, &players).unwrap();
+        assert_eq!(first.outcome, run_game(&config, &players).unwrap().outcome);
+        assert!(first.turns > 0 && first.turns <= 200);
+    }
+
+    #[test]
+    fn royale_shrinks_one_side_at_a_time() {
+        let config = EngineConfig { ruleset: "royale".to_string(), ..EngineConfig::default() };
+        let mut state = initial_state(&config, 2, &mut StdRng::seed_from_u64(1)).unwrap();
+        let mut rng = StdRng::seed_from_u64(2);
+        shrink_royale(&mut state, &mut rng);
+        assert_eq!(state.hazards.len(), 11);
+        shrink_royale(&mut state, &mut rng);
+        assert!(state.hazards.len() == 21 || state.hazards.len() == 22);
+    }
+
+    #[test]
+    fn maps_the_engine_cannot_draw_are_refused() {
+        let players = [Player::Random, Player::Random];
+        for map in ["arcade_maze", "hz_rivers_bridges", "no_such_map"] {
+            let config = EngineConfig { map: map.to_string(), ..EngineConfig::default() };
+            assert!(run_game(&config, &players).is_err(), "{} should be refused", map);
+        }
+        let config = EngineConfig { map: "hz_columns".to_string(), max_turns: 5, ..EngineConfig::default() };
+        assert!(run_game(&config, &players).is_ok());
+    }
+
+    #[test]
+    fn chunked_bodies_are_joined() {
+        assert_eq!(dechunk("4\r\n{\"mo\r\n9\r\nve\":\"up\"}\r\n0\r\n\r\n"), "{\"move\":\"up\"}");
+    }
+
+    #[test]
+    fn snake_specs_parse() {
+        assert!(matches!(Player::parse("search:2", "standard"), Ok(Player::Search { depth: 2, profile: None })));
+        assert!(matches!(Player::parse("http://localhost:8080/", "standard"), Ok(Player::Http(u)) if u == "http://localhost:8080"));
+        assert!(Player::parse("https://example.com", "standard").is_err());
+        assert!(Player::parse("search:2:no_such_file.json", "standard").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::game_state::Coord;
+use super::maps::FoodPlacement;
+use super::state::SimState;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+// Environment variable choosing the food model for lookahead: "off" (default), "expected" or
+// "seeded:<seed>".
+const FOOD_MODEL_ENV: &str = "FOOD_SPAWN_MODEL";
+
+// How SimState::apply_moves spawns food during lookahead.
+#[derive(Debug, Clone, Default)]
+pub enum FoodSpawn {
+    #[default]
+    Off,                 // No new food, as if the board never restocked
+    Seeded(Box<StdRng>), // Engine rules with our own RNG, for rollouts. Cloned states share the future.
+    Expected(f32),       // Expected food: spawn chance builds up until a whole item is due
+}
+
+impl FoodSpawn {
+    pub fn seeded(seed: u64) -> Self {
+        FoodSpawn::Seeded(Box::new(StdRng::seed_from_u64(seed)))
+    }
+
+    // The model named by `FOOD_SPAWN_MODEL`, or Off. The variable is read once per process.
+    pub fn from_env() -> Self {
+        static FROM_ENV: OnceLock<FoodSpawn> = OnceLock::new();
+        FROM_ENV.get_or_init(|| match std::env::var(FOOD_MODEL_ENV).ok().as_deref() {
+            Some("expected") => FoodSpawn::Expected(0.0),
+            Some(s) if s.starts_with("seeded:") => s["seeded:".len()..].parse().map(FoodSpawn::seeded).unwrap_or_default(),
+            _ => FoodSpawn::Off,
+        }).clone()
+    }
+}
+
+// Squares new food may go on: not on a snake, existing food or a hazard, and not where a head can
+// move next turn.
+fn free_squares(state: &SimState) -> Vec<Coord> {
+    let mut blocked: HashSet<Coord> = state.snakes.iter().flat_map(|s| s.body.iter().cloned()).collect();
+    blocked.extend(state.snakes.iter().filter_map(|s| s.head()).flat_map(|h| state.neighbours(h).collect::<Vec<_>>()));
+    (0..state.width)
+        .flat_map(|x| (0..state.height).map(move |y| Coord { x, y }))
+        .filter(|c| !blocked.contains(c) && !state.food.contains(c) && !state.hazards.contains(c))
+        .collect()
+}
+
+// How many items the engine spawns this turn: up to the minimum when short, otherwise one with
+// `foodSpawnChance` percent probability. `roll` is a number in 0..100.
+fn food_due(state: &SimState, roll: u32) -> usize {
+    let minimum = state.rules.minimum_food as usize;
+    if state.food.len() < minimum {
+        minimum - state.food.len()
+    } else if roll < state.rules.food_spawn_chance {
+        1
+    } else {
+        0
+    }
+}
+
+// Whether the rules place food at random at all. Maps with their own spawn squares are left alone.
+fn spawns_randomly(state: &SimState) -> bool {
+    state.rules.map.food == FoodPlacement::Standard && (state.rules.minimum_food > 0 || state.rules.food_spawn_chance > 0)
+}
+
+// Spawns food the way the engine does, drawing from `rng`.
+pub fn spawn_food(state: &mut SimState, rng: &mut StdRng) {
+    if !spawns_randomly(state) {
+        return;
+    }
+    let due = food_due(state, rng.gen_range(0..100));
+    if due == 0 {
+        return;
+    }
+    let mut free = free_squares(state);
+    free.shuffle(rng);
+    state.food.extend(free.into_iter().take(due));
+}
+
+// Spawns food for the turn just played under the state's own model.
+pub fn apply_food_spawn(state: &mut SimState) {
+    match std::mem::take(&mut state.food_spawn) {
+        FoodSpawn::Off => {}
+        FoodSpawn::Seeded(mut rng) => {
+            spawn_food(state, &mut rng);
+            state.food_spawn = FoodSpawn::Seeded(rng);
+        }
+        FoodSpawn::Expected(mut credit) => {
+            if spawns_randomly(state) {
+                // The minimum is topped up at once; the chance of an extra item accumulates.
+                let mut due = food_due(state, 100);
+                if due == 0 {
+                    credit += state.rules.food_spawn_chance as f32 / 100.0;
+                    if credit >= 1.0 {
+                        credit -= 1.0;
+                        due = 1;
+                    }
+                }
+                // Food spawned uniformly at random is, on average, in the middle of the free area,
+                // so expected food stands on the free square nearest the centre.
+                let mut free = free_squares(state);
+                free.sort_by_key(|c| ((2 * c.x - state.width + 1).abs() + (2 * c.y - state.height + 1).abs(), c.x, c.y));
+                state.food.extend(free.into_iter().take(due));
+            }
+            state.food_spawn = FoodSpawn::Expected(credit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Move;
+    use crate::sim::rules::Rules;
+    use crate::sim::state::SimSnake;
+    use std::collections::HashMap;
+
+    fn state(food_spawn: FoodSpawn, minimum_food: u32, food_spawn_chance: u32) -> SimState {
+        SimState {
+            rules: Rules { minimum_food, food_spawn_chance, ..Rules::default() },
+            food_spawn,
+            ..SimState::empty(11, 11).with_snakes(vec![SimSnake::new("me", &[(1, 1), (1, 0), (0, 0)]).with_health(100)])
+        }
+    }
+
+    // Food after each of `turns` turns of moving up.
+    fn play(mut state: SimState, turns: u32) -> Vec<Vec<Coord>> {
+        let moves = HashMap::from([("me".to_string(), Move::Up)]);
+        (0..turns).map(|_| {
+            state = state.apply_moves(&moves);
+            let mut food: Vec<Coord> = state.food.iter().copied().collect();
+            food.sort_by_key(|c| (c.x, c.y));
+            food
+        }).collect()
+    }
+
+    #[test]
+    fn seeded_spawns_are_reproducible() {
+        let first = play(state(FoodSpawn::seeded(7), 1, 50), 8);
+        assert_eq!(first, play(state(FoodSpawn::seeded(7), 1, 50), 8));
+        assert_ne!(first, play(state(FoodSpawn::seeded(8), 1, 50), 8));
+    }
+
+    #[test]
+    fn minimum_food_is_enforced() {
+        for food in play(state(FoodSpawn::seeded(3), 3, 0), 5) {
+            assert_eq!(food.len(), 3);
+        }
+        assert!(play(state(FoodSpawn::Off, 3, 0), 5).iter().all(|food| food.is_empty()));
+    }
+
+    #[test]
+    fn expected_food_builds_up_from_the_spawn_chance() {
+        let food = play(state(FoodSpawn::Expected(0.0), 0, 50), 4);
+        assert_eq!(food.iter().map(|f| f.len()).collect::<Vec<_>>(), vec![0, 1, 1, 2]);
+        assert_eq!(food[1], vec![Coord { x: 5, y: 5 }]);
+        assert_eq!(food, play(state(FoodSpawn::Expected(0.0), 0, 50), 4));
+    }
+
+    #[test]
+    fn no_food_spawns_in_constrictor() {
+        let constrictor = state(FoodSpawn::seeded(1), 1, 15).with_ruleset("constrictor");
+        assert!(play(constrictor, 3).iter().all(|food| food.is_empty()));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::game_state::Coord;
+use log::warn;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+// Turns between new hazard squares on hz_spiral.
+const SPIRAL_EVERY: u32 = 3;
+
+// How a map's hazards change over a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HazardModel {
+    None,                  // No map hazards (the ruleset may still add some, as royale does)
+    Static(StaticLayout),  // Laid out on turn 0 and never changed
+    Spiral { every: u32 }, // One more square every `every` turns, spiralling out from a start square
+}
+
+// Hazard layouts that are drawn once, at the start of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaticLayout {
+    InnerWall, // A ring two squares in from the edge
+    Rings,     // Concentric rings, every other ring from two squares in
+    Columns,   // Every square with odd x and odd y
+    AsShown,   // A hand-drawn layout we do not reproduce; the board shows it every turn
+}
+
+impl StaticLayout {
+    // The layout's squares on a `width` x `height` board, or None if we cannot draw it.
+    pub fn squares(&self, width: i32, height: i32) -> Option<HashSet<Coord>> {
+        let squares = (0..width).flat_map(|x| (0..height).map(move |y| Coord { x, y }));
+        // Distance in from the nearest edge, 0 on the edge itself.
+        let depth = |c: &Coord| c.x.min(c.y).min(width - 1 - c.x).min(height - 1 - c.y);
+        match self {
+            StaticLayout::InnerWall => Some(squares.filter(|c| depth(c) == 2).collect()),
+            StaticLayout::Rings => Some(squares.filter(|c| depth(c) >= 2 && depth(c) % 2 == 0).collect()),
+            StaticLayout::Columns => Some(squares.filter(|c| c.x % 2 == 1 && c.y % 2 == 1).collect()),
+            StaticLayout::AsShown => None,
+        }
+    }
+}
+
+// Where a map places new food.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoodPlacement {
+    Standard, // Randomly on empty squares, per the ruleset's minimumFood and foodSpawnChance
+    Fixed,    // Only on the map's own food spawn squares, which we do not know: none is projected
+}
+
+// Layout rules of one of the engine's built-in maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapRules {
+    pub name: &'static str,
+    pub hazards: HazardModel,
+    pub food: FoodPlacement,
+}
+
+// Maps we know the layout rules of. Anything else plays as standard, with a warning.
+const MAPS: &[MapRules] = &[
+    MapRules { name: "standard", hazards: HazardModel::None, food: FoodPlacement::Standard },
+    MapRules { name: "royale", hazards: HazardModel::None, food: FoodPlacement::Standard }, // Shrinks via Rules
+    // A fixed maze with fixed food squares, both hand-drawn for its one board size.
+    MapRules { name: "arcade_maze", hazards: HazardModel::Static(StaticLayout::AsShown), food: FoodPlacement::Fixed },
+    MapRules { name: "hz_inner_wall", hazards: HazardModel::Static(StaticLayout::InnerWall), food: FoodPlacement::Standard },
+    // The rings are all drawn at setup; none is added during the game.
+    MapRules { name: "hz_rings", hazards: HazardModel::Static(StaticLayout::Rings), food: FoodPlacement::Standard },
+    MapRules { name: "hz_columns", hazards: HazardModel::Static(StaticLayout::Columns), food: FoodPlacement::Standard },
+    MapRules { name: "hz_rivers_bridges", hazards: HazardModel::Static(StaticLayout::AsShown), food: FoodPlacement::Standard },
+    MapRules { name: "hz_spiral", hazards: HazardModel::Spiral { every: SPIRAL_EVERY }, food: FoodPlacement::Standard },
+];
+
+impl Default for MapRules {
+    fn default() -> Self {
+        MAPS[0]
+    }
+}
+
+impl MapRules {
+    // Rules for the map named `name`, if it is one we know.
+    pub fn known(name: &str) -> Option<Self> {
+        MAPS.iter().find(|m| m.name == name).copied()
+    }
+
+    // Rules for the map named `name` in the game info. Unknown maps play as standard, with a
+    // warning the first time each one is seen; an empty name is the standard map.
+    pub fn from_name(name: &str) -> Self {
+        if let Some(map) = MapRules::known(name) {
+            return map;
+        }
+        if !name.is_empty() {
+            static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+            let mut warned = WARNED.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+            if warned.insert(name.to_string()) {
+                warn!("Unknown map '{}'; playing it as standard", name);
+            }
+        }
+        MapRules::default()
+    }
+
+    // Whether the map turns another square to hazard at the start of `turn`.
+    pub fn adds_hazard_on(&self, turn: u32) -> bool {
+        match self.hazards {
+            HazardModel::Spiral { every } => every > 0 && turn > 0 && turn.is_multiple_of(every),
+            HazardModel::None | HazardModel::Static(_) => false,
+        }
+    }
+
+    // Hazards on the board at the start of a game, or None if the map's layout is one we cannot
+    // draw. Spiral maps start empty; see `hazard_layout`.
+    pub fn initial_hazards(&self, width: i32, height: i32) -> Option<HashSet<Coord>> {
+        match self.hazards {
+            HazardModel::Static(layout) => layout.squares(width, height),
+            HazardModel::None | HazardModel::Spiral { .. } => Some(HashSet::new()),
+        }
+    }
+
+    // Every square the map will turn to hazard over a game, in order, for a spiral starting at
+    // `start` and first heading along `first` (0 up, 1 right, 2 down, 3 left). The engine picks
+    // these at random; a local engine draws them itself. Empty for maps whose hazards never grow.
+    pub fn hazard_layout(&self, width: i32, height: i32, start: Coord, first: usize, clockwise: bool) -> VecDeque<Coord> {
+        match self.hazards {
+            HazardModel::Spiral { .. } => spiral(width, height, start, first % 4, clockwise, (width * height) as usize).into(),
+            HazardModel::None | HazardModel::Static(_) => VecDeque::new(),
+        }
+    }
+
+    // Up to `count` squares the map will turn to hazard next, in order, going by the hazards on
+    // the board now. Empty when the map's hazards never grow or the pattern cannot be recognised.
+    pub fn upcoming_hazards(&self, width: i32, height: i32, hazards: &HashSet<Coord>, count: usize) -> VecDeque<Coord> {
+        match self.hazards {
+            HazardModel::Spiral { .. } => continue_spiral(width, height, hazards, count),
+            HazardModel::None | HazardModel::Static(_) => VecDeque::new(),
+        }
+    }
+}
+
+// Spiral directions in clockwise order (y grows upwards).
+const SPIRAL_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+// On-board squares of a square spiral from `start`, first heading along direction `first` and
+// turning clockwise or anticlockwise, up to `limit` squares.
+fn spiral(width: i32, height: i32, start: Coord, first: usize, clockwise: bool, limit: usize) -> Vec<Coord> {
+    let on_board = |c: &Coord| c.x >= 0 && c.x < width && c.y >= 0 && c.y < height;
+    let max_steps = (2 * width.max(height) + 1).pow(2) as usize;
+    let mut squares = vec![start];
+    let (mut at, mut direction, mut leg, mut steps) = (start, first, 1, 0);
+    while squares.len() < limit && steps < max_steps {
+        // Legs run 1, 1, 2, 2, 3, 3, ... squares, turning after each.
+        for _ in 0..2 {
+            let (dx, dy) = SPIRAL_DIRECTIONS[direction];
+            for _ in 0..leg {
+                at = Coord { x: at.x + dx, y: at.y + dy };
+                steps += 1;
+                if on_board(&at) && squares.len() < limit {
+                    squares.push(at);
+                }
+            }
+            direction = if clockwise { (direction + 1) % 4 } else { (direction + 3) % 4 };
+        }
+        leg += 1;
+    }
+    squares
+}
+
+// Finds the spiral whose first squares are exactly `hazards` and returns the `count` squares after
+// them. The start square and direction come from the hazards seen so far, since the engine picks
+// them at random.
+fn continue_spiral(width: i32, height: i32, hazards: &HashSet<Coord>, count: usize) -> VecDeque<Coord> {
+    let laid = hazards.len();
+    if laid == 0 {
+        return VecDeque::new();
+    }
+    // Candidates in a fixed order, so an ambiguous (short) spiral always continues the same way.
+    let mut starts: Vec<Coord> = hazards.iter().copied().collect();
+    starts.sort_by_key(|c| (c.x, c.y));
+    for start in starts {
+        for first in 0..4 {
+            for clockwise in [true, false] {
+                let squares = spiral(width, height, start, first, clockwise, laid + count);
+                if squares.len() >= laid && squares[..laid].iter().all(|c| hazards.contains(c)) {
+                    return squares[laid..].iter().copied().collect();
+                }
+            }
+        }
+    }
+    VecDeque::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spiral_continues_from_the_hazards_laid_so_far() {
+        // Anticlockwise from (5, 5), heading right first: (5,5) (6,5) (6,6) (5,6) (4,6) (4,5) ...
+        let laid: HashSet<Coord> = [(5, 5), (6, 5), (6, 6), (5, 6), (4, 6)].iter().map(|&(x, y)| Coord { x, y }).collect();
+        let upcoming = MapRules::from_name("hz_spiral").upcoming_hazards(11, 11, &laid, 3);
+        assert_eq!(upcoming, [(4, 5), (4, 4), (5, 4)].iter().map(|&(x, y)| Coord { x, y }).collect::<VecDeque<_>>());
+    }
+
+    #[test]
+    fn static_and_unknown_maps_never_grow() {
+        let laid: HashSet<Coord> = [Coord { x: 1, y: 1 }].into_iter().collect();
+        assert!(MapRules::from_name("hz_rings").upcoming_hazards(11, 11, &laid, 3).is_empty());
+        assert_eq!(MapRules::from_name("no_such_map"), MapRules::default());
+        assert!(!MapRules::from_name("arcade_maze").adds_hazard_on(3));
+        assert!(MapRules::from_name("hz_spiral").adds_hazard_on(3));
+    }
+
+    #[test]
+    fn static_layouts_match_the_engine() {
+        let count = |map: &str| MapRules::from_name(map).initial_hazards(11, 11).map(|h| h.len());
+        assert_eq!(count("hz_inner_wall"), Some(24)); // The 7x7 ring two in
+        assert_eq!(count("hz_rings"), Some(24 + 8)); // Plus the 3x3 ring four in
+        assert_eq!(count("hz_columns"), Some(25));
+        assert_eq!(count("standard"), Some(0));
+        assert_eq!(count("arcade_maze"), None);
+
+        let rings = MapRules::from_name("hz_rings").initial_hazards(11, 11).unwrap();
+        assert!(rings.contains(&Coord { x: 2, y: 5 }) && rings.contains(&Coord { x: 4, y: 5 }));
+        assert!(!rings.contains(&Coord { x: 3, y: 5 }) && !rings.contains(&Coord { x: 5, y: 5 }));
+    }
+}
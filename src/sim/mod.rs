@@ -1 +1,6 @@
-pub mod state; 
\ No newline at end of file
+pub mod state;
+pub mod rules;
+pub mod maps;
+pub mod food;
+pub mod engine;
+pub mod stats;
\ No newline at end of file
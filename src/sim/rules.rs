@@ -0,0 +1,99 @@
+use crate::game_state::{RulesetSettings, SquadSettings};
+use super::maps::MapRules;
+
+// Ruleset variants that change how snakes move, collide or grow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    Standard,
+    Solo,
+    Royale,
+    Squad,
+    Constrictor, // Snakes grow every turn and never starve
+    Wrapped,     // Moving off one edge enters from the opposite edge
+}
+
+impl GameMode {
+    // Mode for a ruleset name as sent by the engine. Unknown rulesets play as standard.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "solo" => GameMode::Solo,
+            "royale" => GameMode::Royale,
+            "squad" => GameMode::Squad,
+            "constrictor" => GameMode::Constrictor,
+            "wrapped" => GameMode::Wrapped,
+            _ => GameMode::Standard,
+        }
+    }
+}
+
+// The rules a SimState is played under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub mode: GameMode,
+    pub hazard_damage: u32, // Health lost per turn spent in a hazard, on top of the normal 1
+    pub shrink_every: u32,  // Turns between royale hazard shrinks; 0 when the hazard never grows
+    pub squad: Option<SquadSettings>, // Squad rules, in squad games only
+    pub map: MapRules,
+    pub minimum_food: u32,      // Food is topped up to this many items
+    pub food_spawn_chance: u32, // Percent chance of one extra item on a turn that is not short
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::from_ruleset("standard", &RulesetSettings::default())
+    }
+}
+
+impl Rules {
+    pub fn from_ruleset(name: &str, settings: &RulesetSettings) -> Self {
+        let mode = GameMode::from_name(name);
+        let shrink_every = if mode == GameMode::Royale { settings.royale.shrink_every_n_turns } else { 0 };
+        let squad = if mode == GameMode::Squad { Some(settings.squad) } else { None };
+        // Constrictor boards carry no food at all.
+        let (minimum_food, food_spawn_chance) = if mode == GameMode::Constrictor {
+            (0, 0)
+        } else {
+            (settings.minimum_food, settings.food_spawn_chance)
+        };
+        Rules {
+            mode,
+            hazard_damage: settings.hazard_damage_per_turn,
+            shrink_every,
+            squad,
+            map: MapRules::default(),
+            minimum_food,
+            food_spawn_chance,
+        }
+    }
+
+    // The same rules, played on the engine map named `map`.
+    pub fn with_map(self, map: &str) -> Self {
+        Rules { map: MapRules::from_name(map), ..self }
+    }
+
+    // Whether the royale hazard grows in at the start of `turn`.
+    pub fn shrinks_on(&self, turn: u32) -> bool {
+        self.shrink_every > 0 && turn > 0 && turn.is_multiple_of(self.shrink_every)
+    }
+
+    pub fn is_wrapped(&self) -> bool {
+        self.mode == GameMode::Wrapped
+    }
+
+    // Whether every snake grows every turn, as if it had eaten (constrictor).
+    pub fn grows_every_turn(&self) -> bool {
+        self.mode == GameMode::Constrictor
+    }
+
+    // Whether health drops each turn. In constrictor health is topped up every turn instead.
+    pub fn snakes_starve(&self) -> bool {
+        self.mode != GameMode::Constrictor
+    }
+
+    // Whether tail tips move out of the way each turn. Snakes that grow every turn never move
+    // their tails.
+    pub fn tails_vacate(&self) -> bool {
+        !self.grows_every_turn()
+    }
+}
@@ -1,4 +1,6 @@
-use crate::game_state::{Coord, GameState, Move, Battlesnake as ApiBattlesnake};
+use crate::game_state::{Battlesnake, Board, Coord, Game, GameState, Move};
+use super::food::{self, FoodSpawn};
+use super::rules::Rules;
 use std::collections::{VecDeque, HashMap, HashSet}; // Added HashMap, HashSet
 
 /// Lightweight representation of a snake for simulation.
@@ -8,6 +10,7 @@ pub struct SimSnake {
     pub health: u32,
     pub body: VecDeque<Coord>, // Use VecDeque for efficient head/tail operations
                               // Length is implicitly body.len()
+    pub squad: String,        // Empty outside squad games
 }
 
 impl SimSnake {
@@ -19,9 +22,23 @@ impl SimSnake {
         self.body.len()
     }
 
-    // Helper to check if a coord is part of this snake's body
-    fn occupies(&self, coord: &Coord) -> bool {
-        self.body.contains(coord)
+    // Moves the head onto `next_head`. A snake that eats grows the way the engine does it: the tail
+    // moves on as usual and the new tail segment is doubled, so the tail stays put next turn.
+    pub fn advance(&mut self, next_head: Coord, ate: bool) {
+        self.body.push_front(next_head);
+        self.body.pop_back();
+        if ate {
+            if let Some(&tail) = self.body.back() {
+                self.body.push_back(tail);
+            }
+        }
+    }
+
+    // Whether the last two segments share a square, i.e. the snake ate last turn and its tail tip
+    // will not move out of the way this turn.
+    pub fn tail_is_stacked(&self) -> bool {
+        let n = self.body.len();
+        n > 1 && self.body[n - 1] == self.body[n - 2]
     }
 }
 
@@ -33,14 +50,21 @@ pub struct SimState {
     pub snakes: Vec<SimSnake>,
     pub food: HashSet<Coord>, // Use HashSet for faster food lookups
     pub turn: u32,             // Keep track for debugging/context
-    // TODO: Add hazards if needed by ruleset
+    pub hazards: HashSet<Coord>,
+    pub upcoming_hazards: VecDeque<Coord>, // Squares the map turns to hazard next, in order
+    pub projected_hazards: HashMap<Coord, u32>, // Squares a royale shrink in the lookahead may have turned to hazard, by how many of the four sides would do it
+    pub rules: Rules,
+    pub food_spawn: FoodSpawn, // What food appears after each simulated turn
 }
 
+// How many future map hazard squares to project, far more than any lookahead reaches.
+const PROJECTED_MAP_HAZARDS: usize = 16;
+
 impl SimState {
     /// Placeholder for converting the full GameState from the API
     /// into a lightweight SimState for the search algorithm.
     pub fn from_api_state(api_state: &GameState) -> Self {
-        SimState {
+        let mut state = SimState {
             width: api_state.board.width,
             height: api_state.board.height,
             snakes: api_state.board.snakes.iter().map(|api_snake| {
@@ -48,11 +72,52 @@ impl SimState {
                     id: api_snake.id.clone(),
                     health: api_snake.health,
                     body: api_snake.body.iter().cloned().collect(), // Convert Vec to VecDeque
+                    squad: api_snake.squad.clone(),
                 }
             }).collect(),
             food: api_state.board.food.iter().cloned().collect(), // Convert Vec to HashSet
             turn: api_state.turn,
-        }
+            hazards: api_state.board.hazards.iter().cloned().collect(),
+            upcoming_hazards: VecDeque::new(),
+            projected_hazards: HashMap::new(),
+            rules: Rules::from_ruleset(&api_state.game.ruleset.name, &api_state.game.ruleset.settings)
+                .with_map(&api_state.game.map),
+            food_spawn: FoodSpawn::from_env(),
+        };
+        state.upcoming_hazards = state.rules.map.upcoming_hazards(state.width, state.height, &state.hazards, PROJECTED_MAP_HAZARDS);
+        state
+    }
+
+    // The request the engine would send snake `you_id` for this position, or None once it is out.
+    // Food and hazards are listed in a fixed order so repeated games look the same.
+    pub fn to_api_state(&self, game: &Game, you_id: &str) -> Option<GameState> {
+        let to_api = |snake: &SimSnake| Battlesnake {
+            id: snake.id.clone(),
+            name: snake.id.clone(),
+            health: snake.health,
+            body: snake.body.iter().cloned().collect(),
+            head: snake.head().copied().unwrap_or(Coord { x: 0, y: 0 }),
+            length: snake.length() as u32,
+            squad: snake.squad.clone(),
+        };
+        let sorted = |squares: &HashSet<Coord>| {
+            let mut squares: Vec<Coord> = squares.iter().copied().collect();
+            squares.sort_by_key(|c| (c.x, c.y));
+            squares
+        };
+        let you = self.snakes.iter().find(|s| s.id == you_id)?;
+        Some(GameState {
+            game: game.clone(),
+            turn: self.turn,
+            board: Board {
+                height: self.height,
+                width: self.width,
+                food: sorted(&self.food),
+                hazards: sorted(&self.hazards),
+                snakes: self.snakes.iter().map(to_api).collect(),
+            },
+            you: to_api(you),
+        })
     }
 
     /// Simulates one turn of the game based on the provided moves.
@@ -71,15 +136,10 @@ impl SimState {
                 Some(h) => h,
                 None => continue, // Snake already effectively dead (empty body)
             };
-            // Use provided move or default to a non-moving state (e.g., current head)
-            // In a real search, we'd likely have moves for all snakes or prune dead branches
-            let chosen_move = moves.get(&snake.id).copied().unwrap_or_else(|| {
-                // Default behavior if no move provided (e.g., assume 'up' or stay still?)
-                // For simulation, maybe assume it continues straight or uses a simple heuristic?
-                // Let's assume 'up' for now as a placeholder default if a snake's move is missing.
-                Move::Up
-            });
-            next_head_positions.insert(snake.id.clone(), current_head.apply_move(chosen_move));
+            // Use provided move, or assume 'up' if a snake's move is missing. In a real search
+            // we'd likely have moves for all snakes or prune dead branches.
+            let chosen_move = moves.get(&snake.id).copied().unwrap_or(Move::Up);
+            next_head_positions.insert(snake.id.clone(), self.wrap(current_head.apply_move(chosen_move)));
         }
 
         // 2. Food Consumption
@@ -94,14 +154,24 @@ impl SimState {
             }
         }
         next_state.food = next_state.food.difference(&food_to_remove).cloned().collect();
+        if !self.rules.snakes_starve() {
+            for snake in &mut next_state.snakes {
+                snake.health = 100;
+            }
+        } else {
+            // Hazard damage for heads ending in a hazard without eating
+            for snake in &mut next_state.snakes {
+                let in_hazard = next_head_positions.get(&snake.id).is_some_and(|h| self.hazards.contains(h));
+                if in_hazard && !ate_food.contains(&snake.id) {
+                    snake.health = snake.health.saturating_sub(self.rules.hazard_damage);
+                }
+            }
+        }
 
         // 3. Move snake bodies (Grow or Shrink)
         for snake in &mut next_state.snakes {
              if let Some(next_head) = next_head_positions.get(&snake.id) {
-                 snake.body.push_front(*next_head); // Add new head
-                 if !ate_food.contains(&snake.id) {
-                     snake.body.pop_back(); // Remove tail if no food eaten
-                 }
+                 snake.advance(*next_head, ate_food.contains(&snake.id) || self.rules.grows_every_turn());
              }
         }
 
@@ -139,6 +209,7 @@ impl SimState {
                 // Check against other snakes' bodies (excluding their heads)
                 for other_snake in &next_state.snakes {
                     if snake.id == other_snake.id || died_this_turn.contains(&other_snake.id) { continue; }
+                    if next_state.can_pass_through(snake, other_snake) { continue; }
                     if other_snake.body.iter().skip(1).any(|segment| segment == head) {
                         died_this_turn.insert(snake.id.clone());
                         break;
@@ -178,18 +249,321 @@ impl SimState {
             }
         }
 
-        // 5. Remove dead snakes
+        // 5. Remove dead snakes, with their whole squad under shared elimination
+        if self.rules.squad.is_some_and(|s| s.shared_elimination) {
+            let fallen_squads: HashSet<String> = next_state.snakes.iter()
+                .filter(|s| died_this_turn.contains(&s.id) && !s.squad.is_empty())
+                .map(|s| s.squad.clone())
+                .collect();
+            for snake in &next_state.snakes {
+                if fallen_squads.contains(&snake.squad) {
+                    died_this_turn.insert(snake.id.clone());
+                }
+            }
+        }
         next_state.snakes.retain(|snake| !died_this_turn.contains(&snake.id));
+        next_state.share_squad_attributes();
+
+        // 6. Royale: the hazard grows in for the next turn
+        if self.rules.shrinks_on(next_state.turn) {
+            next_state.project_shrink();
+        }
+
+        // 7. Maps with growing hazards lay their next square
+        if self.rules.map.adds_hazard_on(next_state.turn) {
+            if let Some(square) = next_state.upcoming_hazards.pop_front() {
+                next_state.hazards.insert(square);
+            }
+        }
+
+        // 8. New food, if the state models it
+        food::apply_food_spawn(&mut next_state);
 
         next_state
     }
 
+    // Whether snakes `a` and `b` are on the same squad. A snake is not its own ally.
+    pub fn are_allies(&self, a: &SimSnake, b: &SimSnake) -> bool {
+        self.rules.squad.is_some() && a.id != b.id && !a.squad.is_empty() && a.squad == b.squad
+    }
+
+    // Whether snake `mover` survives running into the body of snake `other`.
+    pub fn can_pass_through(&self, mover: &SimSnake, other: &SimSnake) -> bool {
+        self.rules.squad.is_some_and(|s| s.allow_body_collisions) && self.are_allies(mover, other)
+    }
+
+    // The snakes playing against snake `our_id`: everyone except it and its squadmates.
+    pub fn opponents<'a>(&'a self, our_id: &'a str) -> impl Iterator<Item = &'a SimSnake> + 'a {
+        let you = self.snakes.iter().find(|s| s.id == our_id);
+        self.snakes.iter().filter(move |s| s.id != our_id && !you.is_some_and(|y| self.are_allies(y, s)))
+    }
+
+    // Squad games with shared health or length: every squadmate takes the best value in its squad.
+    // Longer bodies are matched by stacking the tail.
+    fn share_squad_attributes(&mut self) {
+        let squad_rules = match self.rules.squad {
+            Some(s) if s.shared_health || s.shared_length => s,
+            _ => return,
+        };
+        let mut best: HashMap<String, (u32, usize)> = HashMap::new();
+        for snake in self.snakes.iter().filter(|s| !s.squad.is_empty()) {
+            let entry = best.entry(snake.squad.clone()).or_insert((0, 0));
+            *entry = (entry.0.max(snake.health), entry.1.max(snake.length()));
+        }
+        for snake in &mut self.snakes {
+            let (health, length) = match best.get(&snake.squad) {
+                Some(&b) => b,
+                None => continue,
+            };
+            if squad_rules.shared_health {
+                snake.health = health;
+            }
+            if squad_rules.shared_length {
+                while let (true, Some(&tail)) = (snake.length() < length, snake.body.back()) {
+                    snake.body.push_back(tail);
+                }
+            }
+        }
+    }
+
+    // Corners (min, max) of the smallest rectangle holding every square outside the hazard. None
+    // once the hazard covers the board.
+    pub fn safe_zone(&self) -> Option<(Coord, Coord)> {
+        let safe = (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| Coord { x, y }))
+            .filter(|c| !self.hazards.contains(c));
+        safe.fold(None, |zone, c| match zone {
+            None => Some((c, c)),
+            Some((lo, hi)) => Some((
+                Coord { x: lo.x.min(c.x), y: lo.y.min(c.y) },
+                Coord { x: hi.x.max(c.x), y: hi.y.max(c.y) },
+            )),
+        })
+    }
+
+    // Expected royale shrink. The engine moves in one side of the safe zone, chosen with its own
+    // seed, so each side is equally likely to be next. Rather than guess, we record for every rim
+    // square how many of the four sides would cover it (two at a corner, one elsewhere) and leave
+    // the real hazards alone, so nothing is charged damage for a side that may never come in.
+    // Evaluation weighs the odds instead.
+    pub fn project_shrink(&mut self) {
+        let (lo, hi) = match self.safe_zone() {
+            Some(zone) => zone,
+            None => return,
+        };
+        for x in lo.x..=hi.x {
+            for y in lo.y..=hi.y {
+                let sides = [x == lo.x, x == hi.x, y == lo.y, y == hi.y].iter().filter(|&&s| s).count() as u32;
+                if sides > 0 {
+                    let odds = self.projected_hazards.entry(Coord { x, y }).or_insert(0);
+                    *odds = (*odds).max(sides);
+                }
+            }
+        }
+    }
+
+    // Chance, in quarters, that `coord` is a hazard square: 4 once it is one, otherwise the odds a
+    // projected royale shrink has covered it.
+    pub fn hazard_quarters(&self, coord: &Coord) -> u32 {
+        if self.hazards.contains(coord) {
+            4
+        } else {
+            self.projected_hazards.get(coord).map_or(0, |&sides| sides.min(4))
+        }
+    }
+
      // Helper to check if a coordinate is within bounds
     pub fn in_bounds(&self, coord: &Coord) -> bool {
         coord.x >= 0 && coord.x < self.width && coord.y >= 0 && coord.y < self.height
     }
 
+    // Brings a coordinate one step off the board back onto the opposite edge in wrapped mode.
+    // Other modes leave it where it is, off the board.
+    pub fn wrap(&self, coord: Coord) -> Coord {
+        if self.rules.is_wrapped() {
+            Coord { x: coord.x.rem_euclid(self.width), y: coord.y.rem_euclid(self.height) }
+        } else {
+            coord
+        }
+    }
+
+    // The square a move from `coord` leads to, or None if it leaves the board.
+    pub fn step(&self, coord: &Coord, direction: Move) -> Option<Coord> {
+        Some(self.wrap(coord.apply_move(direction))).filter(|c| self.in_bounds(c))
+    }
+
+    // The move that takes us from `from` to the adjacent square `to`, across an edge in wrapped
+    // games, or None if they are not adjacent.
+    pub fn direction_to(&self, from: &Coord, to: &Coord) -> Option<Move> {
+        [Move::Up, Move::Down, Move::Left, Move::Right]
+            .into_iter()
+            .find(|&m| self.step(from, m) == Some(*to))
+    }
+
+    // On-board neighbours of `coord`, in Up, Down, Left, Right order.
+    pub fn neighbours(&self, coord: &Coord) -> impl Iterator<Item = Coord> + '_ {
+        let coord = *coord;
+        [Move::Up, Move::Down, Move::Left, Move::Right].into_iter().filter_map(move |m| self.step(&coord, m))
+    }
+
     // TODO: Add helper methods as needed for collision detection, etc.
     // pub fn is_occupied(&self, coord: &Coord) -> bool { ... }
 
-} 
\ No newline at end of file
+} 
+
+// Hand-built positions for tests: an empty board under the standard rules, with snakes, food and
+// hazards placed by the test.
+#[cfg(test)]
+fn coords(cs: &[(i32, i32)]) -> impl Iterator<Item = Coord> + '_ {
+    cs.iter().map(|&(x, y)| Coord { x, y })
+}
+
+#[cfg(test)]
+impl SimSnake {
+    // A snake with 90 health, outside any squad. `body` runs from head to tail.
+    pub fn new(id: &str, body: &[(i32, i32)]) -> Self {
+        SimSnake { id: id.to_string(), health: 90, body: coords(body).collect(), squad: String::new() }
+    }
+
+    pub fn with_health(mut self, health: u32) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn with_squad(mut self, squad: &str) -> Self {
+        self.squad = squad.to_string();
+        self
+    }
+}
+
+#[cfg(test)]
+impl SimState {
+    // An empty `width` x `height` board on turn 0, under the standard rules with food spawning off.
+    pub fn empty(width: i32, height: i32) -> Self {
+        SimState {
+            width,
+            height,
+            snakes: Vec::new(),
+            food: HashSet::new(),
+            turn: 0,
+            hazards: HashSet::new(),
+            upcoming_hazards: VecDeque::new(),
+            projected_hazards: HashMap::new(),
+            rules: Rules::default(),
+            food_spawn: FoodSpawn::Off,
+        }
+    }
+
+    // The named ruleset with its default settings.
+    pub fn with_ruleset(mut self, name: &str) -> Self {
+        self.rules = Rules::from_ruleset(name, &crate::game_state::RulesetSettings::default());
+        self
+    }
+
+    pub fn with_snakes(mut self, snakes: Vec<SimSnake>) -> Self {
+        self.snakes = snakes;
+        self
+    }
+
+    pub fn with_food(mut self, food: &[(i32, i32)]) -> Self {
+        self.food = coords(food).collect();
+        self
+    }
+
+    pub fn with_hazards(mut self, hazards: &[(i32, i32)]) -> Self {
+        self.hazards = coords(hazards).collect();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Synthetic payloads, written by hand in the engine's format, for consecutive turns: on turn 41
+    // we eat the food at (2, 4), so on turn 42 our tail is stacked on (2, 3) and stays there for
+    // another turn.
+    const BEFORE_EATING: &str = include_str!("../../testdata/before_eating.json");
+    const AFTER_EATING: &str = include_str!("../../testdata/after_eating.json");
+
+    fn payload(json: &str) -> SimState {
+        SimState::from_api_state(&serde_json::from_str(json).expect("valid payload"))
+    }
+
+    // An 11x11 board under `ruleset` with its default settings.
+    fn state(ruleset: &str, snakes: Vec<SimSnake>) -> SimState {
+        SimState::empty(11, 11).with_ruleset(ruleset).with_snakes(snakes)
+    }
+
+    fn moves(moves: &[(&str, Move)]) -> HashMap<String, Move> {
+        moves.iter().map(|&(id, m)| (id.to_string(), m)).collect()
+    }
+
+    #[test]
+    fn simulator_eats_like_the_engine() {
+        let before = payload(BEFORE_EATING);
+        let expected = payload(AFTER_EATING);
+        let after = before.apply_moves(&moves(&[("me", Move::Left), ("them", Move::Up)]));
+        assert_eq!(after.snakes, expected.snakes);
+        assert_eq!(after.food, expected.food);
+
+        // The stacked tail is still there on the next turn.
+        let next = after.apply_moves(&moves(&[("me", Move::Down), ("them", Move::Up)]));
+        assert!(next.snakes.iter().all(|s| s.id != "me"));
+    }
+
+    #[test]
+    fn simulator_grows_every_turn_in_constrictor() {
+        let mut sim_state = state("constrictor", vec![SimSnake::new("me", &[(5, 5), (5, 4), (5, 3)]).with_health(100)]);
+        for _ in 0..3 {
+            sim_state = sim_state.apply_moves(&moves(&[("me", Move::Right)]));
+        }
+        let me = &sim_state.snakes[0];
+        assert_eq!(me.length(), 6);
+        assert_eq!(me.health, 100);
+        assert_eq!(me.body.back(), Some(&Coord { x: 5, y: 4 }));
+    }
+
+    #[test]
+    fn simulator_shrinks_royale_hazard() {
+        // The left column is already hazard; the next shrink comes at the start of turn 25.
+        let hazards: Vec<(i32, i32)> = (0..11).map(|y| (0, y)).collect();
+        let mut sim_state = state("royale", vec![SimSnake::new("me", &[(1, 5), (2, 5), (3, 5)]).with_health(50)])
+            .with_hazards(&hazards);
+        sim_state.turn = 24;
+        assert_eq!(sim_state.safe_zone(), Some((Coord { x: 1, y: 0 }, Coord { x: 10, y: 10 })));
+
+        // Only one side will come in, so the rim is projected as odds, not hazard: one side in
+        // four for an edge square, two at a corner. Moving along the rim costs nothing extra.
+        let after = sim_state.apply_moves(&moves(&[("me", Move::Up)]));
+        assert_eq!(after.snakes[0].health, 50 - 1);
+        assert_eq!(after.hazards, sim_state.hazards);
+        assert_eq!(after.safe_zone(), sim_state.safe_zone());
+        assert_eq!(after.projected_hazards.len(), 38);
+        assert_eq!(after.hazard_quarters(&Coord { x: 0, y: 5 }), 4);
+        assert_eq!(after.hazard_quarters(&Coord { x: 1, y: 6 }), 1);
+        assert_eq!(after.hazard_quarters(&Coord { x: 10, y: 10 }), 2);
+        assert_eq!(after.hazard_quarters(&Coord { x: 2, y: 5 }), 0);
+
+        // Real hazard still hurts.
+        let after = after.apply_moves(&moves(&[("me", Move::Left)]));
+        assert_eq!(after.snakes[0].health, 50 - 1 - 1 - 14);
+    }
+
+    #[test]
+    fn simulator_shares_squad_fate() {
+        let sim_state = state("squad", vec![
+            SimSnake::new("me", &[(5, 5), (5, 4), (5, 3)]).with_squad("red"),
+            SimSnake::new("mate", &[(0, 9), (0, 8), (0, 7), (0, 6)]).with_squad("red").with_health(40),
+            SimSnake::new("them", &[(9, 9), (9, 8), (9, 7)]).with_squad("blue"),
+        ]);
+
+        let after = sim_state.apply_moves(&moves(&[("me", Move::Up), ("mate", Move::Up), ("them", Move::Up)]));
+        let me = after.snakes.iter().find(|s| s.id == "me").unwrap();
+        assert_eq!((me.health, me.length()), (89, 4));
+
+        // The mate runs into the wall and takes us with it.
+        let after = after.apply_moves(&moves(&[("me", Move::Up), ("mate", Move::Left), ("them", Move::Left)]));
+        assert_eq!(after.snakes.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), vec!["them"]);
+    }
+}
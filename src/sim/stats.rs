@@ -0,0 +1,188 @@
+use super::engine::GameOutcome;
+
+// Elo differences are capped here; a perfect score has no finite rating gap.
+const MAX_ELO: f64 = 800.0;
+const RATING_ITERATIONS: usize = 200;
+
+// Wins, draws and losses of one side of a match.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Records a game in which our side sat at `seat`.
+    pub fn record(&mut self, outcome: GameOutcome, seat: usize) {
+        match outcome {
+            GameOutcome::Winner(winner) if winner == seat => self.wins += 1,
+            GameOutcome::Winner(_) => self.losses += 1,
+            GameOutcome::Draw => self.draws += 1,
+        }
+    }
+
+    // The same match seen from the other side.
+    pub fn reversed(&self) -> MatchScore {
+        MatchScore { wins: self.losses, draws: self.draws, losses: self.wins }
+    }
+
+    // Points per game, counting a draw as half a win.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+
+    // Per-game variance of the 1 / 0.5 / 0 results around `score`.
+    fn variance(&self) -> f64 {
+        let mean = self.score();
+        (self.wins as f64 * (1.0 - mean).powi(2)
+            + self.draws as f64 * (0.5 - mean).powi(2)
+            + self.losses as f64 * mean.powi(2)) / self.games().max(1) as f64
+    }
+
+    // 95% confidence interval of `score`.
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        let margin = 1.96 * (self.variance() / self.games().max(1) as f64).sqrt();
+        let mean = self.score();
+        ((mean - margin).max(0.0), (mean + margin).min(1.0))
+    }
+
+    // Elo difference implied by `score`, with its 95% confidence interval.
+    pub fn elo(&self) -> (f64, f64, f64) {
+        let (low, high) = self.confidence_interval();
+        (elo_from_score(self.score()), elo_from_score(low), elo_from_score(high))
+    }
+}
+
+// Rating difference at which the stronger side is expected to score `score`.
+pub fn elo_from_score(score: f64) -> f64 {
+    if score <= 0.0 {
+        return -MAX_ELO;
+    }
+    if score >= 1.0 {
+        return MAX_ELO;
+    }
+    (-400.0 * (1.0 / score - 1.0).log10()).clamp(-MAX_ELO, MAX_ELO)
+}
+
+// Expected score of the side `elo` points stronger.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// Elo ratings, averaging 0, that best explain a round robin (the Bradley-Terry maximum likelihood
+// fit, draws counting half). `scores[i][j]` is player i's score against player j. Players who
+// won or lost everything are held at the rating cap.
+pub fn ratings(scores: &[Vec<MatchScore>]) -> Vec<f64> {
+    let n = scores.len();
+    let points: Vec<f64> = scores.iter()
+        .map(|row| row.iter().map(|s| s.wins as f64 + 0.5 * s.draws as f64).sum())
+        .collect();
+    let cap = 10f64.powf(MAX_ELO / 400.0);
+    let mut strength = vec![1.0; n];
+    for _ in 0..RATING_ITERATIONS {
+        let previous = strength.clone();
+        for i in 0..n {
+            let expected: f64 = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| scores[i][j].games() as f64 / (previous[i] + previous[j]))
+                .sum();
+            if expected > 0.0 {
+                strength[i] = (points[i] / expected).clamp(1.0 / cap, cap);
+            }
+        }
+        // Fix the geometric mean at 1 so ratings average 0.
+        let mean = strength.iter().map(|s| s.ln()).sum::<f64>() / n.max(1) as f64;
+        strength.iter_mut().for_each(|s| *s /= mean.exp());
+    }
+    strength.iter().map(|s| (400.0 * s.log10()).clamp(-MAX_ELO, MAX_ELO)).collect()
+}
+
+// What a sequential probability ratio test concludes so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    Continue,
+    Accept, // The patch is at least elo1 stronger
+    Reject, // The patch is no more than elo0 stronger
+}
+
+// Sequential probability ratio test of H0 "the patch gains elo0" against H1 "it gains elo1",
+// with false positive rate `alpha` and false negative rate `beta`. Games are played until the log
+// likelihood ratio leaves the (lower, upper) bounds, so a clear result needs far fewer games than
+// a fixed-length match.
+#[derive(Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Sprt { elo0, elo1, lower: (beta / (1.0 - alpha)).ln(), upper: ((1.0 - beta) / alpha).ln() }
+    }
+
+    // Log likelihood ratio of the patch's results, using the normal approximation to the score.
+    // Zero until the results vary, since identical results give no variance to go by.
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let variance = score.variance();
+        if variance <= 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (score_from_elo(self.elo0), score_from_elo(self.elo1));
+        score.games() as f64 * (s1 - s0) * (2.0 * score.score() - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn decide(&self, score: &MatchScore) -> SprtDecision {
+        let llr = self.llr(score);
+        if llr >= self.upper {
+            SprtDecision::Accept
+        } else if llr <= self.lower {
+            SprtDecision::Reject
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        MatchScore { wins, draws, losses }
+    }
+
+    #[test]
+    fn elo_and_score_convert_both_ways() {
+        assert!((elo_from_score(0.5)).abs() < 1e-9);
+        assert!((elo_from_score(score_from_elo(100.0)) - 100.0).abs() < 1e-6);
+        assert_eq!(elo_from_score(1.0), MAX_ELO);
+    }
+
+    #[test]
+    fn ratings_order_a_round_robin() {
+        // a beats b 3-1, b beats c 3-1, a beats c 4-0.
+        let scores = vec![
+            vec![score(0, 0, 0), score(3, 0, 1), score(4, 0, 0)],
+            vec![score(1, 0, 3), score(0, 0, 0), score(3, 0, 1)],
+            vec![score(0, 0, 4), score(1, 0, 3), score(0, 0, 0)],
+        ];
+        let elo = ratings(&scores);
+        assert!(elo[0] > elo[1] && elo[1] > elo[2]);
+        assert!(elo.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn sprt_accepts_clear_gains_and_rejects_clear_losses() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        assert_eq!(sprt.decide(&score(10, 0, 10)), SprtDecision::Continue);
+        assert_eq!(sprt.decide(&score(700, 100, 200)), SprtDecision::Accept);
+        assert_eq!(sprt.decide(&score(200, 100, 700)), SprtDecision::Reject);
+    }
+}